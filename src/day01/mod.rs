@@ -1,20 +1,180 @@
-fn parse_input(input: &str) -> Vec<u32> {
-    let mut lines = input.lines().peekable();
-    let mut result: Vec<u32> = Vec::new();
-    while lines.peek().is_some() {
-        result.push((&mut lines).map_while(|s| s.parse::<u32>().ok()).sum());
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    /// Line number of the offending line, from 1
+    line: usize,
+    text: String,
+}
+
+/// Split the input into the items carried by each elf.  Groups are separated
+/// by blank lines; runs of several blank lines don't create empty elves.
+fn parse_elves(input: &str) -> Result<Vec<Vec<u32>>, ParseError> {
+    let mut result = Vec::new();
+    let mut current = Vec::new();
+    for (line, n) in input.lines().zip(1..) {
+        // `lines` already drops a trailing '\r', but not other whitespace
+        let text = line.trim();
+        if text.is_empty() {
+            if !current.is_empty() {
+                result.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(text.parse().map_err(|_| ParseError {
+                line: n,
+                text: String::from(line),
+            })?);
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    Ok(result)
+}
+
+fn parse_input(input: &str) -> Result<Vec<u32>, ParseError> {
+    Ok(parse_elves(input)?.iter().map(|e| e.iter().sum()).collect())
+}
+
+/// The `k` largest values, biggest first
+fn top_k(values: &[u32], k: usize) -> Vec<u32> {
+    // Min-heap of the best so far, so the smallest of them is easy to replace
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for &value in values {
+        heap.push(Reverse(value));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec().into_iter().map(|r| r.0).collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct ElfStats {
+    /// Position in the input, from 1
+    index: usize,
+    items: usize,
+    total: u32,
+    /// Position when sorted by total, from 1.  Elves with equal totals share a
+    /// rank.
+    rank: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Report {
+    /// Every elf, in rank order
+    elves: Vec<ElfStats>,
+    mean: f64,
+    median: f64,
+}
+
+impl Report {
+    pub fn new(elves: &[Vec<u32>]) -> Self {
+        let mut stats: Vec<_> = elves
+            .iter()
+            .zip(1..)
+            .map(|(items, index)| ElfStats {
+                index,
+                items: items.len(),
+                total: items.iter().sum(),
+                rank: 0,
+            })
+            .collect();
+        stats.sort_by_key(|e| Reverse(e.total));
+        for i in 0..stats.len() {
+            stats[i].rank = match i {
+                _ if i > 0 && stats[i - 1].total == stats[i].total => stats[i - 1].rank,
+                _ => i + 1,
+            };
+        }
+
+        let n = stats.len();
+        let mean = stats.iter().map(|e| e.total as f64).sum::<f64>() / n as f64;
+        let median = match n {
+            0 => f64::NAN,
+            _ if n % 2 == 1 => stats[n / 2].total as f64,
+            _ => (stats[n / 2 - 1].total as f64 + stats[n / 2].total as f64) / 2.0,
+        };
+
+        Report {
+            elves: stats,
+            mean,
+            median,
+        }
+    }
+
+    /// Only keep the `k` highest ranked elves in the listing
+    pub fn truncate(&mut self, k: usize) {
+        self.elves.truncate(k);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:>5} {:>5} {:>8}",
+            "rank", "elf", "items", "total"
+        )?;
+        for elf in self.elves.iter() {
+            writeln!(
+                f,
+                "{:>5} {:>5} {:>5} {:>8}",
+                elf.rank, elf.index, elf.items, elf.total
+            )?;
+        }
+        write!(f, "mean: {:.1}, median: {:.1}", self.mean, self.median)
     }
-    result
 }
 
 fn part1(input: &str) -> u32 {
-    *parse_input(input).iter().max().unwrap()
+    top_k(&parse_input(input).expect("invalid input"), 1)[0]
 }
 
 fn part2(input: &str) -> u32 {
-    let mut sums = parse_input(input);
-    sums.sort();
-    sums.iter().skip(sums.len() - 3).sum()
+    top_k(&parse_input(input).expect("invalid input"), 3)
+        .iter()
+        .sum()
+}
+
+/// Parse the optional `k` argument to the commands below
+fn parse_k(args: &[String]) -> Option<usize> {
+    match args.first() {
+        Some(arg) => arg.parse().ok(),
+        None => Some(3),
+    }
+}
+
+/// `top [k]`: the calories carried by the `k` best-stocked elves
+pub fn top(args: &[String]) {
+    let Some(k) = parse_k(args) else {
+        return println!("usage: top [k]");
+    };
+    let sums = match parse_input(include_str!("input.txt")) {
+        Ok(sums) => sums,
+        Err(e) => return println!("line {}: invalid entry {:?}", e.line, e.text),
+    };
+    let best = top_k(&sums, k);
+    for (total, rank) in best.iter().zip(1..) {
+        println!("{}: {}", rank, total);
+    }
+    println!("sum: {}", best.iter().sum::<u32>());
+}
+
+/// `report [k]`: per-elf statistics for the `k` best-stocked elves
+pub fn report(args: &[String]) {
+    let Some(k) = parse_k(args) else {
+        return println!("usage: report [k]");
+    };
+    let elves = match parse_elves(include_str!("input.txt")) {
+        Ok(elves) => elves,
+        Err(e) => return println!("line {}: invalid entry {:?}", e.line, e.text),
+    };
+    let mut report = Report::new(&elves);
+    report.truncate(k);
+    println!("{}", report);
 }
 
 pub fn main() {
@@ -31,7 +191,58 @@ mod test {
 
     #[test]
     fn test_parse_input() {
-        assert_eq!(parse_input(INPUT), vec![6000, 4000, 11000, 24000, 10000]);
+        assert_eq!(
+            parse_input(INPUT),
+            Ok(vec![6000, 4000, 11000, 24000, 10000])
+        );
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        let input = "1000\r\n2000  \r\n\r\n\n 3000\n   \n";
+        assert_eq!(parse_elves(input), Ok(vec![vec![1000, 2000], vec![3000]]));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let error = ParseError {
+            line: 3,
+            text: String::from("12x"),
+        };
+        assert_eq!(parse_input("1\n\n12x\n4"), Err(error));
+
+        let error = ParseError {
+            line: 2,
+            text: String::from("-5"),
+        };
+        assert_eq!(parse_input("1\r\n-5\r\n"), Err(error));
+    }
+
+    #[test]
+    fn test_top_k() {
+        let sums = parse_input(INPUT).unwrap();
+        assert_eq!(top_k(&sums, 0), vec![]);
+        assert_eq!(top_k(&sums, 2), vec![24000, 11000]);
+        assert_eq!(top_k(&sums, 10), vec![24000, 11000, 10000, 6000, 4000]);
+    }
+
+    #[test]
+    fn test_report() {
+        let report = Report::new(&parse_elves(INPUT).unwrap());
+        let first = ElfStats {
+            index: 4,
+            items: 3,
+            total: 24000,
+            rank: 1,
+        };
+        assert_eq!(report.elves[0], first);
+        assert_eq!(report.mean, 11000.0);
+        assert_eq!(report.median, 10000.0);
+
+        let report = Report::new(&[vec![1, 2], vec![3], vec![5]]);
+        let ranks: Vec<_> = report.elves.iter().map(|e| e.rank).collect();
+        assert_eq!(ranks, vec![1, 2, 2]);
+        assert_eq!(report.median, 3.0);
     }
 
     #[test]
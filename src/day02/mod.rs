@@ -1,30 +1,32 @@
-use std::ops::{BitAnd, BitOr};
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
+mod tournament;
+
+use tournament::{Always, Cycle, FrequencyCounter, Guide, Random, Strategy, WinStayLoseShift};
+
 #[derive(Debug, PartialEq)]
 struct InputError;
 
-#[derive(Clone, Debug, PartialEq)]
-enum Rps {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3,
+/// A line of a strategy guide which couldn't be read
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    /// Line number of the offending line, from 1
+    line: usize,
+    text: String,
 }
 
-impl FromStr for Rps {
-    type Err = InputError;
+/// A weapon, identified by its position in the game's cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Weapon(usize);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(Rps::Rock),
-            "B" | "Y" => Ok(Rps::Paper),
-            "C" | "Z" => Ok(Rps::Scissors),
-            _ => Err(InputError),
-        }
+impl Weapon {
+    pub fn score(&self) -> i32 {
+        self.0 as i32 + 1
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Outcome {
     Loss = 0,
     Draw = 3,
@@ -44,59 +46,358 @@ impl FromStr for Outcome {
     }
 }
 
-impl BitOr for &Rps {
-    type Output = Outcome;
+/// A cyclic hand game with an odd number of weapons.  Each weapon beats the
+/// ones an odd number of steps behind it in the cycle and loses to the rest,
+/// so every weapon beats exactly half of the others.
+///
+/// In a strategy guide the opponent's weapons are written with the first
+/// letters of the alphabet and yours with the last ones (`ABC` and `XYZ` for
+/// the usual game), or either can be given by name.
+#[derive(Debug)]
+struct Game {
+    names: Vec<String>,
+    theirs: Vec<char>,
+    yours: Vec<char>,
+}
 
-    fn bitor(self, other: Self) -> Self::Output {
-        match (self, other) {
-            (Rps::Rock, Rps::Paper) => Outcome::Loss,
-            (Rps::Paper, Rps::Scissors) => Outcome::Loss,
-            (Rps::Scissors, Rps::Rock) => Outcome::Loss,
-            (Rps::Rock, Rps::Scissors) => Outcome::Win,
-            (Rps::Paper, Rps::Rock) => Outcome::Win,
-            (Rps::Scissors, Rps::Paper) => Outcome::Win,
-            _ => Outcome::Draw,
+impl Game {
+    pub fn new(names: Vec<String>) -> Self {
+        let n = names.len();
+        assert!(n % 2 == 1, "cyclic games need an odd number of weapons");
+        assert!(n >= 3, "cyclic games need at least three weapons to win");
+        assert!(n <= 13, "not enough letters for more than 13 weapons");
+        Game {
+            names,
+            theirs: (b'A'..).take(n).map(char::from).collect(),
+            yours: (b'A'..=b'Z').skip(26 - n).map(char::from).collect(),
         }
     }
-}
 
-impl BitAnd<&Rps> for &Outcome {
-    type Output = Rps;
+    /// Rock, Paper, Scissors
+    pub fn rps() -> Self {
+        Self::new(["Rock", "Paper", "Scissors"].map(String::from).to_vec())
+    }
+
+    /// Rock, Paper, Scissors, Spock, Lizard
+    pub fn rpsls() -> Self {
+        let names = ["Rock", "Paper", "Scissors", "Spock", "Lizard"];
+        Self::new(names.map(String::from).to_vec())
+    }
+
+    /// A game with `n` anonymous weapons, named by number
+    pub fn cyclic(n: usize) -> Self {
+        Self::new((1..=n).map(|i| i.to_string()).collect())
+    }
 
-    fn bitand(self, other: &Rps) -> Self::Output {
-        match (self, other) {
-            (Outcome::Draw, rps) => rps.clone(),
-            (Outcome::Win, Rps::Rock) => Rps::Paper,
-            (Outcome::Win, Rps::Paper) => Rps::Scissors,
-            (Outcome::Win, Rps::Scissors) => Rps::Rock,
-            (Outcome::Loss, Rps::Rock) => Rps::Scissors,
-            (Outcome::Loss, Rps::Paper) => Rps::Rock,
-            (Outcome::Loss, Rps::Scissors) => Rps::Paper,
+    pub fn size(&self) -> usize {
+        self.names.len()
+    }
+
+    /// The outcome for you, when you play `you` against `them`
+    pub fn outcome(&self, you: Weapon, them: Weapon) -> Outcome {
+        match (you.0 + self.size() - them.0) % self.size() {
+            0 => Outcome::Draw,
+            d if d % 2 == 1 => Outcome::Win,
+            _ => Outcome::Loss,
         }
     }
+
+    /// The weapon to play against `them` to get the given outcome.  With more
+    /// than three weapons there are several ways to win or lose, in which case
+    /// this picks one next to `them` in the cycle.
+    pub fn required(&self, outcome: Outcome, them: Weapon) -> Weapon {
+        let n = self.size();
+        Weapon(match outcome {
+            Outcome::Draw => them.0,
+            Outcome::Win => (them.0 + 1) % n,
+            Outcome::Loss => (them.0 + n - 1) % n,
+        })
+    }
+
+    /// Look up a weapon by its letter in the given column, or by name
+    fn weapon(&self, letters: &[char], s: &str) -> Result<Weapon, InputError> {
+        let mut chars = s.chars();
+        let index = match (chars.next(), chars.next()) {
+            (Some(c), None) => letters.iter().position(|l| *l == c),
+            _ => self.names.iter().position(|n| n.eq_ignore_ascii_case(s)),
+        };
+        index.map(Weapon).ok_or(InputError)
+    }
 }
 
-fn parse<T: FromStr>(line: &str) -> (Rps, T) {
-    let (left, right) = line.split_once(" ").expect("invalid input");
-    let left = left.parse().expect("invalid input");
-    let right = right.parse().ok().expect("invalid input");
-    (left, right)
+/// Something that can appear in the second column of a strategy guide
+trait Decode: Sized {
+    fn decode(game: &Game, s: &str) -> Result<Self, InputError>;
 }
 
-fn part1(input: &str) -> i32 {
+impl Decode for Weapon {
+    fn decode(game: &Game, s: &str) -> Result<Self, InputError> {
+        game.weapon(&game.yours, s)
+    }
+}
+
+impl Decode for Outcome {
+    fn decode(_: &Game, s: &str) -> Result<Self, InputError> {
+        s.parse()
+    }
+}
+
+/// The opponent's weapon on a line of a guide, and the rest of the line
+fn split<'a>(game: &Game, line: &'a str) -> Result<(Weapon, &'a str), InputError> {
+    let (left, right) = line.split_once(' ').ok_or(InputError)?;
+    Ok((game.weapon(&game.theirs, left)?, right))
+}
+
+fn parse<T: Decode>(game: &Game, line: &str) -> Result<(Weapon, T), InputError> {
+    let (left, right) = split(game, line)?;
+    Ok((left, T::decode(game, right)?))
+}
+
+/// Read every line of a guide with `read`, stopping at the first bad one
+fn parse_lines<'a, T>(
+    input: &'a str,
+    read: impl Fn(&'a str) -> Result<T, InputError>,
+) -> Result<Vec<T>, ParseError> {
     input
         .lines()
-        .map(parse)
-        .map(|(them, you)| (&you | &them) as i32 + you as i32)
-        .sum()
+        .zip(1..)
+        .map(|(line, n)| {
+            read(line).map_err(|_| ParseError {
+                line: n,
+                text: String::from(line),
+            })
+        })
+        .collect()
+}
+
+/// Total score when the second column says what to play
+fn play(game: &Game, input: &str) -> Result<i32, ParseError> {
+    let rounds = parse_lines(input, |line| parse::<Weapon>(game, line))?;
+    Ok(rounds
+        .into_iter()
+        .map(|(them, you)| game.outcome(you, them) as i32 + you.score())
+        .sum())
+}
+
+/// Total score when the second column says how the round should end
+fn follow(game: &Game, input: &str) -> Result<i32, ParseError> {
+    let rounds = parse_lines(input, |line| parse::<Outcome>(game, line))?;
+    Ok(rounds
+        .into_iter()
+        .map(|(them, outcome)| game.required(outcome, them).score() + outcome as i32)
+        .sum())
+}
+
+/// A possible meaning of a letter in the second column of a guide
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Meaning {
+    Move(Weapon),
+    Outcome(Outcome),
+}
+
+/// One way of reading the second column of a guide, and the score it gives
+#[derive(Debug, PartialEq)]
+struct Interpretation {
+    mapping: Vec<(String, Meaning)>,
+    score: i32,
+}
+
+impl Interpretation {
+    pub fn describe(&self, game: &Game) -> String {
+        let mapping: Vec<_> = self
+            .mapping
+            .iter()
+            .map(|(letter, meaning)| match meaning {
+                Meaning::Move(weapon) => format!("{}={}", letter, game.names[weapon.0]),
+                Meaning::Outcome(outcome) => format!("{}={:?}", letter, outcome),
+            })
+            .collect();
+        format!("{}: {}", mapping.join(" "), self.score)
+    }
+}
+
+/// Every way of picking `k` distinct items out of `n`, in lexicographic order
+fn arrangements(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for prefix in arrangements(n, k - 1) {
+        for i in (0..n).filter(|i| !prefix.contains(i)) {
+            let mut next = prefix.clone();
+            next.push(i);
+            result.push(next);
+        }
+    }
+    result
+}
+
+/// Score a guide under every possible meaning of its second column: each
+/// letter standing for a different move, or each for a different outcome
+fn interpretations(game: &Game, input: &str) -> Result<Vec<Interpretation>, ParseError> {
+    let lines = parse_lines(input, |line| split(game, line))?;
+    let letters: Vec<_> = lines
+        .iter()
+        .map(|(_, right)| *right)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let rounds: Vec<_> = lines
+        .iter()
+        .map(|(them, right)| (*them, letters.iter().position(|l| l == right).unwrap()))
+        .collect();
+
+    let moves: Vec<_> = (0..game.size()).map(|i| Meaning::Move(Weapon(i))).collect();
+    let outcomes: Vec<_> = [Outcome::Loss, Outcome::Draw, Outcome::Win]
+        .map(Meaning::Outcome)
+        .to_vec();
+
+    let mut result = Vec::new();
+    for options in [moves, outcomes] {
+        for arrangement in arrangements(options.len(), letters.len()) {
+            let meanings: Vec<_> = arrangement.iter().map(|i| options[*i]).collect();
+            let score = rounds
+                .iter()
+                .map(|(them, i)| match meanings[*i] {
+                    Meaning::Move(you) => game.outcome(you, *them) as i32 + you.score(),
+                    Meaning::Outcome(outcome) => {
+                        game.required(outcome, *them).score() + outcome as i32
+                    }
+                })
+                .sum();
+            result.push(Interpretation {
+                mapping: letters
+                    .iter()
+                    .map(|l| l.to_string())
+                    .zip(meanings)
+                    .collect(),
+                score,
+            });
+        }
+    }
+    Ok(result)
+}
+
+fn part1(input: &str) -> i32 {
+    play(&Game::rps(), input).expect("invalid input")
 }
 
 fn part2(input: &str) -> i32 {
-    input
+    follow(&Game::rps(), input).expect("invalid input")
+}
+
+/// Read the `<rps|rpsls|N> [file]` arguments shared by the commands below
+fn game_and_input(args: &[String]) -> Result<(Game, String), String> {
+    let game = match args.first().map(|s| s.as_str()) {
+        Some("rps") => Game::rps(),
+        Some("rpsls") => Game::rpsls(),
+        Some(n) => match n.parse() {
+            Ok(n) if n % 2 == 1 && (3..=13).contains(&n) => Game::cyclic(n),
+            _ => {
+                return Err(String::from(
+                    "game must be rps, rpsls or an odd number from 3 to 13",
+                ))
+            }
+        },
+        None => return Err(String::from("missing game")),
+    };
+    let input = match args.get(1) {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?
+        }
+        None => String::from(include_str!("input.txt")),
+    };
+    Ok((game, input))
+}
+
+/// `score <rps|rpsls|N> [file]`: score a strategy guide for a larger game
+pub fn score(args: &[String]) {
+    let (game, input) = match game_and_input(args) {
+        Ok(result) => result,
+        Err(e) => return println!("{}\nusage: score <rps|rpsls|N> [file]", e),
+    };
+    // The second column may only make sense one way
+    match play(&game, &input) {
+        Ok(score) => println!("moves: {}", score),
+        Err(e) => println!("moves: line {}: invalid round {:?}", e.line, e.text),
+    }
+    match follow(&game, &input) {
+        Ok(score) => println!("outcomes: {}", score),
+        Err(e) => println!("outcomes: line {}: invalid round {:?}", e.line, e.text),
+    }
+}
+
+/// `decode <rps|rpsls|N> [file] [expected]`: score a guide under every
+/// meaning of its second column, and find the ones giving `expected`
+pub fn decode(args: &[String]) {
+    let (game, input) = match game_and_input(args) {
+        Ok(result) => result,
+        Err(e) => return println!("{}\nusage: decode <rps|rpsls|N> [file] [expected]", e),
+    };
+    let expected = match args.get(2).map(|s| s.parse::<i32>()) {
+        Some(Ok(n)) => Some(n),
+        Some(Err(_)) => return println!("expected score must be a number"),
+        None => None,
+    };
+
+    let all = match interpretations(&game, &input) {
+        Ok(all) => all,
+        Err(e) => return println!("line {}: invalid round {:?}", e.line, e.text),
+    };
+    for interpretation in all.iter() {
+        println!("{}", interpretation.describe(&game));
+    }
+    if let Some(best) = all.iter().max_by_key(|i| i.score) {
+        println!("best: {}", best.describe(&game));
+    }
+    if let Some(worst) = all.iter().min_by_key(|i| i.score) {
+        println!("worst: {}", worst.describe(&game));
+    }
+    if let Some(expected) = expected {
+        let matches: Vec<_> = all.iter().filter(|i| i.score == expected).collect();
+        if matches.is_empty() {
+            println!("nothing scores {}", expected);
+        }
+        for interpretation in matches {
+            println!("matches: {}", interpretation.describe(&game));
+        }
+    }
+}
+
+/// `tournament [rounds] [seed]`: play the built-in strategies against each
+/// other, including one following the puzzle's guide
+pub fn tournament(args: &[String]) {
+    let usage = "usage: tournament [rounds] [seed]";
+    let Ok(rounds) = args.first().map_or(Ok(1000), |s| s.parse()) else {
+        return println!("{}", usage);
+    };
+    let Ok(seed) = args.get(1).map_or(Ok(0), |s| s.parse()) else {
+        return println!("{}", usage);
+    };
+
+    let game = Game::rps();
+    let guide = include_str!("input.txt")
         .lines()
-        .map(parse)
-        .map(|(them, outcome)| (&outcome & &them) as i32 + outcome as i32)
-        .sum()
+        .map(|line| parse::<Weapon>(&game, line).expect("invalid input").1)
+        .collect();
+    let strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(Always(Weapon(0))),
+        Box::new(Cycle),
+        Box::new(Random),
+        Box::new(FrequencyCounter),
+        Box::new(WinStayLoseShift),
+        Box::new(Guide(guide)),
+    ];
+
+    println!("{:<20} {:>8} {:>8}", "strategy", "score", "win rate");
+    for standing in tournament::tournament(&game, &strategies, rounds, seed) {
+        println!(
+            "{:<20} {:>8} {:>7.1}%",
+            standing.name,
+            standing.score,
+            standing.win_rate() * 100.0
+        );
+    }
 }
 
 pub fn main() {
@@ -113,12 +414,100 @@ mod test {
 
     #[test]
     fn test_parse_moves() {
-        assert_eq!(parse("A X"), (Rps::Rock, Rps::Rock));
+        assert_eq!(parse(&Game::rps(), "A X"), Ok((Weapon(0), Weapon(0))));
+        assert_eq!(
+            parse(&Game::rps(), "paper Scissors"),
+            Ok((Weapon(1), Weapon(2)))
+        );
+        assert_eq!(parse::<Weapon>(&Game::rps(), "A Q"), Err(InputError));
     }
 
     #[test]
     fn test_parse_outcome() {
-        assert_eq!(parse("A X"), (Rps::Rock, Outcome::Loss));
+        assert_eq!(parse(&Game::rps(), "A X"), Ok((Weapon(0), Outcome::Loss)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let game = Game::rps();
+        let error = ParseError {
+            line: 2,
+            text: String::new(),
+        };
+        assert_eq!(play(&game, "A X\n\nB Y"), Err(error));
+
+        let error = ParseError {
+            line: 1,
+            text: String::from("A Q"),
+        };
+        assert_eq!(follow(&game, "A Q"), Err(error));
+        assert!(interpretations(&game, "A X\nD Y").is_err());
+    }
+
+    #[test]
+    fn test_rpsls() {
+        let game = Game::rpsls();
+        let beats = [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ];
+        for (winner, loser) in beats {
+            let winner = game.weapon(&game.yours, winner).unwrap();
+            let loser = game.weapon(&game.theirs, loser).unwrap();
+            assert_eq!(game.outcome(winner, loser), Outcome::Win);
+            assert_eq!(game.outcome(loser, winner), Outcome::Loss);
+        }
+
+        // Opponent plays Spock, you play Lizard then lose on purpose
+        assert_eq!(play(&game, "D Z"), Ok(6 + 5));
+        assert_eq!(follow(&game, "D X"), Ok(3));
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let game = Game::cyclic(7);
+        for them in (0..7).map(Weapon) {
+            let wins = (0..7)
+                .map(Weapon)
+                .filter(|you| game.outcome(*you, them) == Outcome::Win)
+                .count();
+            assert_eq!(wins, 3);
+            for outcome in [Outcome::Loss, Outcome::Draw, Outcome::Win] {
+                assert_eq!(game.outcome(game.required(outcome, them), them), outcome);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arrangements() {
+        assert_eq!(arrangements(3, 0), vec![vec![]]);
+        assert_eq!(arrangements(3, 2).len(), 6);
+        assert_eq!(arrangements(3, 3)[1], vec![0, 2, 1]);
+        assert!(arrangements(2, 3).is_empty());
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let game = Game::rps();
+        let all = interpretations(&game, INPUT).unwrap();
+        assert_eq!(all.len(), 12);
+
+        // The puzzle's own readings are among them
+        let described: Vec<_> = all.iter().map(|i| i.describe(&game)).collect();
+        assert!(described.contains(&String::from("X=Rock Y=Paper Z=Scissors: 15")));
+        assert!(described.contains(&String::from("X=Loss Y=Draw Z=Win: 12")));
+
+        let best = all.iter().map(|i| i.score).max();
+        let worst = all.iter().map(|i| i.score).min();
+        assert_eq!((best, worst), (Some(24), Some(6)));
     }
 
     #[test]
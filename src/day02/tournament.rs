@@ -0,0 +1,241 @@
+use super::{Game, Outcome, Weapon};
+
+/// Small xorshift generator, so that tournaments can be repeated from a seed
+#[derive(Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero, and similar seeds should still diverge
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// A way of choosing moves.  The history of a match is given as pairs of
+/// (own move, opponent's move), oldest first.
+pub trait Strategy {
+    fn name(&self, game: &Game) -> String;
+
+    fn choose(&self, game: &Game, history: &[(Weapon, Weapon)], rng: &mut Rng) -> Weapon;
+}
+
+/// Always play the same thing
+pub struct Always(pub Weapon);
+
+impl Strategy for Always {
+    fn name(&self, game: &Game) -> String {
+        format!("always {}", game.names[self.0 .0])
+    }
+
+    fn choose(&self, _: &Game, _: &[(Weapon, Weapon)], _: &mut Rng) -> Weapon {
+        self.0
+    }
+}
+
+/// Play every weapon in turn
+pub struct Cycle;
+
+impl Strategy for Cycle {
+    fn name(&self, _: &Game) -> String {
+        String::from("cycle")
+    }
+
+    fn choose(&self, game: &Game, history: &[(Weapon, Weapon)], _: &mut Rng) -> Weapon {
+        Weapon(history.len() % game.size())
+    }
+}
+
+/// Pick uniformly at random
+pub struct Random;
+
+impl Strategy for Random {
+    fn name(&self, _: &Game) -> String {
+        String::from("random")
+    }
+
+    fn choose(&self, game: &Game, _: &[(Weapon, Weapon)], rng: &mut Rng) -> Weapon {
+        Weapon(rng.below(game.size()))
+    }
+}
+
+/// Beat whatever the opponent has played most often so far
+pub struct FrequencyCounter;
+
+impl Strategy for FrequencyCounter {
+    fn name(&self, _: &Game) -> String {
+        String::from("frequency counter")
+    }
+
+    fn choose(&self, game: &Game, history: &[(Weapon, Weapon)], rng: &mut Rng) -> Weapon {
+        let mut counts = vec![0; game.size()];
+        for (_, theirs) in history {
+            counts[theirs.0] += 1;
+        }
+        match (0..game.size()).max_by_key(|i| (counts[*i], usize::MAX - i)) {
+            Some(i) if counts[i] > 0 => game.required(Outcome::Win, Weapon(i)),
+            _ => Weapon(rng.below(game.size())),
+        }
+    }
+}
+
+/// Keep playing the same weapon after a win, and move on to the next one in
+/// the cycle otherwise
+pub struct WinStayLoseShift;
+
+impl Strategy for WinStayLoseShift {
+    fn name(&self, _: &Game) -> String {
+        String::from("win-stay lose-shift")
+    }
+
+    fn choose(&self, game: &Game, history: &[(Weapon, Weapon)], rng: &mut Rng) -> Weapon {
+        match history.last() {
+            Some((mine, theirs)) if game.outcome(*mine, *theirs) == Outcome::Win => *mine,
+            Some((mine, _)) => Weapon((mine.0 + 1) % game.size()),
+            None => Weapon(rng.below(game.size())),
+        }
+    }
+}
+
+/// Play the moves from a strategy guide in order, starting again at the end
+pub struct Guide(pub Vec<Weapon>);
+
+impl Strategy for Guide {
+    fn name(&self, _: &Game) -> String {
+        String::from("guide")
+    }
+
+    fn choose(&self, _: &Game, history: &[(Weapon, Weapon)], _: &mut Rng) -> Weapon {
+        self.0[history.len() % self.0.len()]
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub score: i64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Standing {
+    /// The fraction of games won, or 0 before any have been played
+    pub fn win_rate(&self) -> f64 {
+        match self.wins + self.draws + self.losses {
+            0 => 0.0,
+            games => self.wins as f64 / games as f64,
+        }
+    }
+
+    fn record(&mut self, game: &Game, mine: Weapon, theirs: Weapon) {
+        let outcome = game.outcome(mine, theirs);
+        self.score += (outcome as i32 + mine.score()) as i64;
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+}
+
+/// Play every strategy against every other for the given number of rounds,
+/// and return the standings sorted by total score
+pub fn tournament(
+    game: &Game,
+    strategies: &[Box<dyn Strategy>],
+    rounds: usize,
+    seed: u64,
+) -> Vec<Standing> {
+    let mut rng = Rng::new(seed);
+    let mut standings: Vec<_> = strategies
+        .iter()
+        .map(|s| Standing {
+            name: s.name(game),
+            ..Default::default()
+        })
+        .collect();
+
+    for i in 0..strategies.len() {
+        for j in i + 1..strategies.len() {
+            // Each player sees the history from their own side
+            let mut history_i = Vec::with_capacity(rounds);
+            let mut history_j = Vec::with_capacity(rounds);
+            for _ in 0..rounds {
+                let a = strategies[i].choose(game, &history_i, &mut rng);
+                let b = strategies[j].choose(game, &history_j, &mut rng);
+                standings[i].record(game, a, b);
+                standings[j].record(game, b, a);
+                history_i.push((a, b));
+                history_j.push((b, a));
+            }
+        }
+    }
+
+    standings.sort_by_key(|s| -s.score);
+    standings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let first: Vec<_> = (0..5).scan(Rng::new(1), |r, _| Some(r.below(3))).collect();
+        let again: Vec<_> = (0..5).scan(Rng::new(1), |r, _| Some(r.below(3))).collect();
+        assert_eq!(first, again);
+        assert!(first.iter().all(|n| *n < 3));
+    }
+
+    #[test]
+    fn test_counter_beats_constant() {
+        let game = Game::rps();
+        let strategies: Vec<Box<dyn Strategy>> =
+            vec![Box::new(Always(Weapon(0))), Box::new(FrequencyCounter)];
+        let standings = tournament(&game, &strategies, 100, 7);
+        assert_eq!(standings[0].name, "frequency counter");
+        assert!(standings[0].wins >= 99);
+        assert_eq!(standings[1].name, "always Rock");
+        assert!(standings[1].win_rate() <= 0.01);
+    }
+
+    #[test]
+    fn test_no_rounds() {
+        let game = Game::rps();
+        let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(Cycle), Box::new(Random)];
+        for standing in tournament(&game, &strategies, 0, 1) {
+            assert_eq!(standing.score, 0);
+            assert_eq!(standing.win_rate(), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_cycle_against_guide() {
+        // The guide plays Paper, Rock, Scissors against Rock, Paper, Scissors
+        let game = Game::rps();
+        let guide = Guide(vec![Weapon(1), Weapon(0), Weapon(2)]);
+        let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(Cycle), Box::new(guide)];
+        let standings = tournament(&game, &strategies, 3, 0);
+        let guide = Standing {
+            name: String::from("guide"),
+            score: 8 + 1 + 6,
+            wins: 1,
+            draws: 1,
+            losses: 1,
+        };
+        assert!(standings.contains(&guide));
+        assert_eq!(standings[0].score, standings[1].score);
+    }
+}
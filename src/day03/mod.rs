@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
 
 fn priority(c: char) -> i32 {
     match c {
@@ -8,41 +9,178 @@ fn priority(c: char) -> i32 {
     }
 }
 
-fn find_duplicate<It>(mut bags: Vec<It>) -> Option<char>
-where
-    It: Iterator<Item = char>,
-{
-    let mut result: Option<HashSet<_>> = None;
-    for bag in bags.iter_mut().map(|b| b.collect()) {
-        result = match result {
-            None => Some(bag),
-            Some(so_far) => Some(so_far.intersection(&bag).map(|c| *c).collect()),
+/// The item with the given priority
+fn item(priority: i32) -> char {
+    match priority {
+        1..=26 => (priority + 96) as u8 as char,
+        27..=52 => (priority + 38) as u8 as char,
+        _ => panic!("unexpected priority"),
+    }
+}
+
+/// A set of items, stored as a bitmask with one bit per priority.  Iterating
+/// over it gives the priorities of the items, lowest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    /// Every item there is
+    pub fn all() -> Self {
+        ItemSet(((1 << 52) - 1) << 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> Items {
+        Items(self.0)
+    }
+}
+
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let items: Vec<_> = self
+            .iter()
+            .map(|p| format!("{} ({})", item(p), p))
+            .collect();
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+impl From<&[u8]> for ItemSet {
+    fn from(items: &[u8]) -> Self {
+        ItemSet(
+            items
+                .iter()
+                .fold(0, |mask, c| mask | 1 << priority(*c as char)),
+        )
+    }
+}
+
+impl From<&str> for ItemSet {
+    fn from(items: &str) -> Self {
+        ItemSet::from(items.as_bytes())
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = ItemSet;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        ItemSet(self.0 & other.0)
+    }
+}
+
+impl BitOr for ItemSet {
+    type Output = ItemSet;
+
+    fn bitor(self, other: Self) -> Self::Output {
+        ItemSet(self.0 | other.0)
+    }
+}
+
+impl IntoIterator for ItemSet {
+    type Item = i32;
+    type IntoIter = Items;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The priorities of the items in an `ItemSet`, lowest first
+#[derive(Clone, Debug)]
+struct Items(u64);
+
+impl Iterator for Items {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            0 => None,
+            mask => {
+                // Take the lowest set bit
+                self.0 &= mask - 1;
+                Some(mask.trailing_zeros() as i32)
+            }
         }
     }
-    match result {
-        Some(result) if result.len() == 1 => Some(*result.iter().next().unwrap()),
+}
+
+/// The priority of the only item shared by all the bags, if there is exactly
+/// one
+fn find_duplicate<It>(bags: It) -> Option<i32>
+where
+    It: Iterator<Item = ItemSet>,
+{
+    let common = bags.fold(ItemSet::all(), |a, b| a & b);
+    match common.len() {
+        1 => common.iter().next(),
         _ => None,
     }
 }
 
-fn search_bag(items: &str) -> char {
-    let len = items.len();
-    let left = items.get(..len / 2).unwrap().chars();
-    let right = items.get(len / 2..).unwrap().chars();
-    find_duplicate(vec![left, right]).unwrap()
+/// The items shared by all the bags in each group of `k` consecutive lines.
+/// The last group is smaller if the number of bags isn't a multiple of `k`.
+fn shared_items(input: &str, k: usize) -> Vec<ItemSet> {
+    let bags: Vec<ItemSet> = input.lines().map(ItemSet::from).collect();
+    bags.chunks(k)
+        .map(|group| group.iter().fold(ItemSet::all(), |a, b| a & *b))
+        .collect()
+}
+
+fn search_bag(items: &str) -> i32 {
+    let (left, right) = items.as_bytes().split_at(items.len() / 2);
+    find_duplicate([left.into(), right.into()].into_iter()).unwrap()
 }
 
 fn part1(input: &str) -> i32 {
-    input.lines().map(search_bag).map(priority).sum()
+    input.lines().map(search_bag).sum()
 }
 
 fn part2(input: &str) -> i32 {
-    let mut lines = input.lines();
-    let mut result = 0;
-    loop {
-        match find_duplicate(lines.by_ref().take(3).map(|e| e.chars()).collect()) {
-            Some(sticker) => result += priority(sticker),
-            None => break result,
+    let bags: Vec<ItemSet> = input.lines().map(ItemSet::from).collect();
+    bags.chunks(3)
+        .map_while(|group| find_duplicate(group.iter().copied()))
+        .sum()
+}
+
+/// `groups [k]`: find the badge for each group of `k` elves, and list the
+/// groups which don't share exactly one item
+pub fn groups(args: &[String]) {
+    let k = match args.first().map(|s| s.parse()) {
+        Some(Ok(k)) if k > 0 => k,
+        None => 3,
+        _ => return println!("usage: groups [k]"),
+    };
+
+    let input = include_str!("input.txt");
+    let lines = input.lines().count();
+    let shared = shared_items(input, k);
+    let total: i32 = shared
+        .iter()
+        .filter(|s| s.len() == 1)
+        .flat_map(|s| s.iter())
+        .sum();
+    println!(
+        "{} groups of {}, badge priorities: {}",
+        shared.len(),
+        k,
+        total
+    );
+
+    for (common, i) in shared.iter().zip(0..) {
+        let range = format!("lines {}-{}", i * k + 1, lines.min((i + 1) * k));
+        if common.is_empty() {
+            println!("group {} ({}): nothing shared", i + 1, range);
+        } else if common.len() > 1 {
+            let n = common.len();
+            println!("group {} ({}): {} shared: {}", i + 1, range, n, common);
         }
     }
 }
@@ -65,6 +203,44 @@ mod test {
         assert_eq!(priority('P'), 42);
     }
 
+    #[test]
+    fn test_item() {
+        for p in 1..=52 {
+            assert_eq!(priority(item(p)), p);
+        }
+    }
+
+    #[test]
+    fn test_shared_items() {
+        let shared = shared_items(INPUT, 3);
+        assert_eq!(shared, vec![ItemSet::from("r"), ItemSet::from("Z")]);
+
+        // Pairs of bags share more than one item
+        let shared = shared_items(INPUT, 2);
+        assert_eq!(
+            shared[0].to_string(),
+            "f (6), r (18), s (19), F (32), M (39)"
+        );
+
+        // Everything together has nothing in common, and the leftover bag
+        // shares all of its items with itself
+        let shared = shared_items(INPUT, 5);
+        assert!(shared[0].is_empty());
+        assert_eq!(shared[1], ItemSet::from("CrZsJsPPZsGzwwsLwLmpwMDw"));
+    }
+
+    #[test]
+    fn test_item_set() {
+        let a = ItemSet::from("abcZ");
+        let b = ItemSet::from("cdZZ");
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), vec![3, 52]);
+        assert_eq!((a | b).len(), 5);
+        assert_eq!(a.into_iter().count(), a.len());
+        assert!((a & ItemSet::from("xyz")).is_empty());
+        assert_eq!(ItemSet::all().len(), 52);
+        assert_eq!(ItemSet::all() & a, a);
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1(INPUT), 157);
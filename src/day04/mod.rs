@@ -1,38 +1,201 @@
-use std::ops::Range;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Sub};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+struct ParseError;
 
 trait Preorder<Rhs = Self> {
     fn preceeds(&self, other: &Rhs) -> bool;
 }
 
-impl<T: PartialOrd> Preorder for Range<T> {
+trait Connected {
+    fn connected(&self, other: &Self) -> bool;
+}
+
+/// A range of sections, including both ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Interval {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Interval {
+    pub fn new(start: i32, end: i32) -> Self {
+        assert!(start <= end, "interval ends before it starts");
+        Interval { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        (self.end - self.start) as usize + 1
+    }
+}
+
+impl Preorder for Interval {
     fn preceeds(&self, other: &Self) -> bool {
         other.start <= self.start && self.end <= other.end
     }
 }
 
-trait Connected {
-    fn connected(&self, other: &Self) -> bool;
+impl Connected for Interval {
+    fn connected(&self, other: &Self) -> bool {
+        other.start <= self.end && self.start <= other.end
+    }
+}
+
+impl FromStr for Interval {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s.split_once('-').ok_or(ParseError)?;
+        match (a.parse(), b.parse()) {
+            (Ok(start), Ok(end)) if start <= end => Ok(Interval { start, end }),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A set of sections, stored as sorted intervals which neither overlap nor
+/// touch
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct IntervalSet(Vec<Interval>);
+
+impl IntervalSet {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Total number of sections covered
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|i| i.len()).sum()
+    }
+
+    pub fn contains(&self, section: i32) -> bool {
+        // Find the last interval starting at or before the section
+        match self.0.partition_point(|i| i.start <= section) {
+            0 => false,
+            n => section <= self.0[n - 1].end,
+        }
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> Self {
+        IntervalSet(vec![interval])
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<It: IntoIterator<Item = Interval>>(iter: It) -> Self {
+        let mut intervals: Vec<_> = iter.into_iter().collect();
+        intervals.sort_by_key(|i| i.start);
+
+        // Merge anything overlapping or adjacent into the previous interval
+        let mut result: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match result.last_mut() {
+                Some(last) if interval.start <= last.end + 1 => {
+                    last.end = last.end.max(interval.end);
+                }
+                _ => result.push(interval),
+            }
+        }
+        IntervalSet(result)
+    }
+}
+
+impl BitOr for &IntervalSet {
+    type Output = IntervalSet;
+
+    fn bitor(self, other: Self) -> Self::Output {
+        self.0.iter().chain(other.0.iter()).copied().collect()
+    }
+}
+
+impl BitAnd for &IntervalSet {
+    type Output = IntervalSet;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.0.len() && j < other.0.len() {
+            let (a, b) = (self.0[i], other.0[j]);
+            if a.connected(&b) {
+                result.push(Interval::new(a.start.max(b.start), a.end.min(b.end)));
+            }
+            // Move past whichever interval finishes first
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet(result)
+    }
+}
+
+impl Sub for &IntervalSet {
+    type Output = IntervalSet;
+
+    fn sub(self, other: Self) -> Self::Output {
+        let mut result = Vec::new();
+        for interval in self.0.iter() {
+            // Chop off pieces from the start, one removed interval at a time
+            let mut rest = Some(*interval);
+            for removed in other.0.iter().filter(|r| r.connected(interval)) {
+                let Some(current) = rest else { break };
+                if current.start < removed.start {
+                    result.push(Interval::new(current.start, removed.start - 1));
+                }
+                rest = match removed.end < current.end {
+                    true => Some(Interval::new(removed.end + 1, current.end)),
+                    false => None,
+                };
+            }
+            result.extend(rest);
+        }
+        IntervalSet(result)
+    }
 }
 
-impl<T: PartialOrd> Connected for Range<T> {
+impl Preorder for IntervalSet {
+    fn preceeds(&self, other: &Self) -> bool {
+        (self - other).is_empty()
+    }
+}
+
+impl Connected for IntervalSet {
     fn connected(&self, other: &Self) -> bool {
-        other.start <= self.end && self.start <= other.end
+        !(self & other).is_empty()
     }
 }
 
-fn parse_line(line: &str) -> (Range<i32>, Range<i32>) {
-    let (left, right) = line.split_once(",").unwrap();
-    let (a, b) = left.split_once("-").unwrap();
-    let (c, d) = right.split_once("-").unwrap();
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<_> = self.0.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+fn parse_line(line: &str) -> (IntervalSet, IntervalSet) {
+    let (left, right) = line.split_once(',').unwrap();
     (
-        a.parse().unwrap()..b.parse().unwrap(),
-        c.parse().unwrap()..d.parse().unwrap(),
+        Interval::from_str(left).unwrap().into(),
+        Interval::from_str(right).unwrap().into(),
     )
 }
 
 fn process<F>(input: &str, func: F) -> usize
 where
-    F: FnMut((Range<i32>, Range<i32>)) -> bool,
+    F: FnMut((IntervalSet, IntervalSet)) -> bool,
 {
     input
         .lines()
@@ -47,7 +210,127 @@ fn part1(input: &str) -> usize {
 }
 
 fn part2(input: &str) -> usize {
-    process(input, |(a, b)| a.connected(&b) || b.connected(&a))
+    process(input, |(a, b)| a.connected(&b))
+}
+
+/// Everything found by sweeping across all the assignments in the camp.
+/// Assignments are referred to by their index in the input.
+#[derive(Debug, Default, PartialEq)]
+struct Survey {
+    /// Pairs which overlap without either containing the other
+    overlapping: Vec<(usize, usize)>,
+    /// Pairs where the first is entirely inside the second
+    contained: Vec<(usize, usize)>,
+    /// Sections between the lowest and highest assigned that nobody covers
+    uncovered: IntervalSet,
+    /// The lowest of the sections assigned to the most elves, and how many
+    busiest: Option<(i32, usize)>,
+}
+
+/// Every assignment in the input, two per line
+fn parse_assignments(input: &str) -> Vec<Interval> {
+    input
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(|s| s.parse().unwrap())
+        .collect()
+}
+
+/// Compare all the assignments at once with a sweep line, in O(n log n) plus
+/// the number of pairs found
+fn survey(assignments: &[Interval]) -> Survey {
+    let mut survey = Survey::default();
+
+    // Visit intervals by start, longest first for equal starts, keeping track
+    // of the ones still going by where they end
+    let mut order: Vec<_> = (0..assignments.len()).collect();
+    order.sort_by_key(|i| (assignments[*i].start, Reverse(assignments[*i].end)));
+    let mut active = BTreeSet::new();
+    for j in order {
+        let next = assignments[j];
+        while let Some((end, _)) = active.first() {
+            match *end < next.start {
+                true => active.pop_first(),
+                false => break,
+            };
+        }
+        // Everything left started no later than this one, so either contains it
+        // or overlaps the start of it
+        for (_, i) in active.iter() {
+            match next.preceeds(&assignments[*i]) {
+                true => survey.contained.push((j, *i)),
+                false => survey.overlapping.push((*i, j)),
+            }
+        }
+        active.insert((next.end, j));
+    }
+
+    // Sweep again over the ends of each interval to count how many elves cover
+    // each section
+    let mut events: Vec<_> = assignments
+        .iter()
+        .flat_map(|a| [(a.start, 1), (a.end + 1, -1)])
+        .collect();
+    events.sort();
+    let mut count = 0;
+    let mut gaps = Vec::new();
+    for (k, (section, delta)) in events.iter().enumerate() {
+        count += delta;
+        // Wait until everything happening at this section has been counted
+        match events.get(k + 1) {
+            Some((next, _)) if next == section => continue,
+            Some((next, _)) if count == 0 => gaps.push(Interval::new(*section, next - 1)),
+            _ => (),
+        }
+        if count > 0 && survey.busiest.is_none_or(|(_, most)| count as usize > most) {
+            survey.busiest = Some((*section, count as usize));
+        }
+    }
+    survey.uncovered = gaps.into_iter().collect();
+
+    survey
+}
+
+/// `camp`: compare every assignment in the camp with every other
+pub fn camp(_: &[String]) {
+    let assignments = parse_assignments(include_str!("input.txt"));
+    let survey = survey(&assignments);
+    println!("assignments: {}", assignments.len());
+    println!("overlapping pairs: {}", survey.overlapping.len());
+    println!("contained pairs: {}", survey.contained.len());
+    println!(
+        "uncovered: {} ({} sections)",
+        survey.uncovered,
+        survey.uncovered.len()
+    );
+    if let Some((section, count)) = survey.busiest {
+        println!("busiest: section {} with {} elves", section, count);
+    }
+}
+
+/// `coverage [section...]`: which sections are assigned to anyone at all, and
+/// whether each of the given sections is
+pub fn coverage(args: &[String]) {
+    let Ok(sections) = args
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<i32>, _>>()
+    else {
+        return println!("usage: coverage [section...]");
+    };
+
+    let covered = include_str!("input.txt")
+        .lines()
+        .map(parse_line)
+        .fold(IntervalSet::default(), |set, (a, b)| &(&set | &a) | &b);
+    println!("covered: {} ({} sections)", covered, covered.len());
+    if let (Some(first), Some(last)) = (covered.0.first(), covered.0.last()) {
+        let gaps = &IntervalSet::from(Interval::new(first.start, last.end)) - &covered;
+        println!("gaps: {} ({} sections)", gaps, gaps.len());
+    }
+    for section in sections {
+        println!("{}: {}", section, covered.contains(section));
+    }
 }
 
 pub fn main() {
@@ -62,11 +345,84 @@ mod test {
 
     const INPUT: &str = include_str!("test.txt");
 
+    fn set(intervals: &[(i32, i32)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|(a, b)| Interval::new(*a, *b))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval_preorder() {
+        assert!(Interval::new(1, 2).preceeds(&Interval::new(1, 2)));
+        assert!(Interval::new(2, 3).preceeds(&Interval::new(1, 4)));
+        assert!(!Interval::new(1, 4).preceeds(&Interval::new(2, 3)));
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!("2-4".parse(), Ok(Interval::new(2, 4)));
+        assert_eq!("6-6".parse::<Interval>().map(|i| i.len()), Ok(1));
+        assert_eq!("4-2".parse::<Interval>(), Err(ParseError));
+        assert_eq!("4".parse::<Interval>(), Err(ParseError));
+    }
+
+    #[test]
+    fn test_set_merges() {
+        assert_eq!(
+            set(&[(5, 7), (1, 3), (4, 4), (10, 12)]),
+            set(&[(1, 7), (10, 12)])
+        );
+        assert_eq!(set(&[(1, 10), (2, 3)]).len(), 10);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(1, 5), (10, 15)]);
+        let b = set(&[(4, 11), (14, 20)]);
+        assert_eq!(&a | &b, set(&[(1, 20)]));
+        assert_eq!(&a & &b, set(&[(4, 5), (10, 11), (14, 15)]));
+        assert_eq!(&a - &b, set(&[(1, 3), (12, 13)]));
+        assert_eq!(&b - &a, set(&[(6, 9), (16, 20)]));
+        assert_eq!(&a - &a, IntervalSet::default());
+    }
+
+    #[test]
+    fn test_set_containment() {
+        let a = set(&[(1, 5), (10, 15)]);
+        assert!(a.contains(1) && a.contains(5) && a.contains(12));
+        assert!(!a.contains(0) && !a.contains(7) && !a.contains(16));
+        assert!(set(&[(2, 3), (11, 15)]).preceeds(&a));
+        assert!(!set(&[(2, 11)]).preceeds(&a));
+        assert!(a.connected(&set(&[(5, 9)])));
+        assert!(!a.connected(&set(&[(6, 9)])));
+    }
+
+    #[test]
+    fn test_survey() {
+        let assignments = [(1, 3), (2, 5), (3, 3), (8, 9)].map(|(a, b)| Interval::new(a, b));
+        let expected = Survey {
+            overlapping: vec![(0, 1)],
+            contained: vec![(2, 0), (2, 1)],
+            uncovered: set(&[(6, 7)]),
+            busiest: Some((3, 3)),
+        };
+        assert_eq!(survey(&assignments), expected);
+    }
+
     #[test]
-    fn test_range_preorder() {
-        assert_eq!((1..2).preceeds(&(1..2)), true);
-        assert_eq!((2..3).preceeds(&(1..4)), true);
-        assert_eq!((1..4).preceeds(&(2..3)), false);
+    fn test_survey_matches_parts() {
+        // Pairs from the same line should be the ones the puzzle counts
+        let survey = survey(&parse_assignments(INPUT));
+        let same_line =
+            |pairs: &Vec<(usize, usize)>| pairs.iter().filter(|(i, j)| i / 2 == j / 2).count();
+        assert_eq!(same_line(&survey.contained), part1(INPUT));
+        assert_eq!(
+            same_line(&survey.contained) + same_line(&survey.overlapping),
+            part2(INPUT)
+        );
+        assert!(survey.uncovered.is_empty());
+        assert_eq!(survey.busiest, Some((6, 8)));
     }
 
     #[test]
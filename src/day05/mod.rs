@@ -1,46 +1,385 @@
-use std::str::Lines;
+use std::fmt;
+use std::str::{FromStr, Lines};
 
 #[derive(Debug, PartialEq)]
+struct ParseError;
+
+/// A single crane instruction, with stacks numbered from 0
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let number = |i: usize| words[i].parse::<usize>().map_err(|_| ParseError);
+        match words[..] {
+            ["move", _, "from", _, "to", _] => Ok(Move {
+                count: number(1)?,
+                from: number(3)?.checked_sub(1).ok_or(ParseError)?,
+                to: number(5)?.checked_sub(1).ok_or(ParseError)?,
+            }),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CraneError {
+    /// A line that isn't a move
+    Parse { line: usize, text: String },
+    /// A move to or from a stack that doesn't exist
+    NoStack { step: Move, stack: usize },
+    /// A move of more crates than there are on the stack
+    TooFew { step: Move, available: usize },
+    /// Any of the above, along with the line of the input it came from
+    Line { line: usize, error: Box<CraneError> },
+}
+
+impl fmt::Display for CraneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CraneError::Parse { line, text } => write!(f, "line {}: invalid move {:?}", line, text),
+            CraneError::NoStack { step, stack } => {
+                write!(f, "{}: there is no stack {}", step, stack + 1)
+            }
+            CraneError::TooFew { step, available } => write!(
+                f,
+                "{}: stack {} only has {} crates",
+                step,
+                step.from + 1,
+                available
+            ),
+            CraneError::Line { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+/// A move as it was actually carried out, so that it can be reversed
+#[derive(Clone, Debug, PartialEq)]
+struct Operation {
+    step: Move,
+    /// The crates taken off the source stack, bottom first
+    taken: Vec<char>,
+    /// The crates as they ended up on the destination stack, bottom first
+    placed: Vec<char>,
+}
+
+/// Where two sets of stacks differ: which stack, and the crates each has above
+/// the bottom part they share
+#[derive(Debug, PartialEq)]
+struct Difference {
+    stack: usize,
+    ours: Vec<char>,
+    theirs: Vec<char>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ours: String = self.ours.iter().collect();
+        let theirs: String = self.theirs.iter().collect();
+        write!(f, "stack {}: [{}] vs [{}]", self.stack + 1, ours, theirs)
+    }
+}
+
+/// The stacks of crates, along with a log of the operations carried out on
+/// them so far and any that have been undone since
+#[derive(Debug, Default)]
 struct Stacks {
     stacks: Vec<Vec<char>>,
+    log: Vec<Operation>,
+    undone: Vec<Operation>,
+}
+
+/// Stacks are equal if their crates are, however they got that way
+impl PartialEq for Stacks {
+    fn eq(&self, other: &Self) -> bool {
+        self.stacks == other.stacks
+    }
 }
 
 impl Stacks {
+    /// Make sure a move can actually be carried out
+    pub fn check(&self, step: &Move) -> Result<(), CraneError> {
+        for stack in [step.from, step.to] {
+            if stack >= self.stacks.len() {
+                return Err(CraneError::NoStack { step: *step, stack });
+            }
+        }
+        let available = self.stacks[step.from].len();
+        match step.count > available {
+            true => Err(CraneError::TooFew {
+                step: *step,
+                available,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Carry out a move with the given crane, or leave the stacks untouched if
+    /// it isn't possible.  Returns the number of lifts it took.
+    pub fn rearrange<C>(&mut self, crane: &mut C, step: &Move) -> Result<usize, CraneError>
+    where
+        C: Crane + ?Sized,
+    {
+        self.check(step)?;
+        let from = &self.stacks[step.from];
+        let taken = from[from.len() - step.count..].to_vec();
+        let lifts = crane.rearrange(&mut self.stacks, step);
+        let to = &self.stacks[step.to];
+        let placed = to[to.len() - step.count..].to_vec();
+
+        // Doing something new means whatever was undone can't be redone
+        self.log.push(Operation {
+            step: *step,
+            taken,
+            placed,
+        });
+        self.undone.clear();
+        Ok(lifts)
+    }
+
+    /// How many operations have been carried out so far
+    pub fn checkpoint(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Reverse the last operation, returning the move it made
+    pub fn undo(&mut self) -> Option<Move> {
+        let op = self.log.pop()?;
+        let to = &mut self.stacks[op.step.to];
+        to.truncate(to.len() - op.placed.len());
+        self.stacks[op.step.from].extend(op.taken.iter());
+        let step = op.step;
+        self.undone.push(op);
+        Some(step)
+    }
+
+    /// Carry out the last undone operation again, returning the move it made
+    pub fn redo(&mut self) -> Option<Move> {
+        let op = self.undone.pop()?;
+        let from = &mut self.stacks[op.step.from];
+        from.truncate(from.len() - op.taken.len());
+        self.stacks[op.step.to].extend(op.placed.iter());
+        let step = op.step;
+        self.log.push(op);
+        Some(step)
+    }
+
+    /// Undo or redo operations until exactly `checkpoint` have been done, or
+    /// as close as possible
+    pub fn seek(&mut self, checkpoint: usize) {
+        while self.checkpoint() > checkpoint && self.undo().is_some() {}
+        while self.checkpoint() < checkpoint && self.redo().is_some() {}
+    }
+
+    /// Go back to a checkpoint and carry out a different list of moves from
+    /// there, stopping at the first one which fails
+    pub fn replay<C>(
+        &mut self,
+        checkpoint: usize,
+        crane: &mut C,
+        moves: &[Move],
+    ) -> Result<usize, CraneError>
+    where
+        C: Crane + ?Sized,
+    {
+        self.seek(checkpoint);
+        moves.iter().map(|step| self.rearrange(crane, step)).sum()
+    }
+
+    /// Compare the crates with another set of stacks
+    pub fn diff(&self, other: &Stacks) -> Vec<Difference> {
+        let count = self.stacks.len().max(other.stacks.len());
+        let empty = Vec::new();
+        (0..count)
+            .filter_map(|stack| {
+                let ours = self.stacks.get(stack).unwrap_or(&empty);
+                let theirs = other.stacks.get(stack).unwrap_or(&empty);
+                let common = ours.iter().zip(theirs).take_while(|(a, b)| a == b).count();
+                match ours.len() == common && theirs.len() == common {
+                    true => None,
+                    false => Some(Difference {
+                        stack,
+                        ours: ours[common..].to_vec(),
+                        theirs: theirs[common..].to_vec(),
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// The crate on top of each stack, with a space for empty ones
     pub fn tops(&self) -> String {
-        self.stacks.iter().map(|s| s.last().unwrap()).collect()
+        self.stacks
+            .iter()
+            .map(|s| s.last().copied().unwrap_or(' '))
+            .collect()
     }
 }
 
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Draw crates row by row from the top, padding missing ones with spaces
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+        for row in (0..height).rev() {
+            let crates: Vec<_> = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(row) {
+                    Some(c) => format!("[{}]", c),
+                    None => String::from("   "),
+                })
+                .collect();
+            writeln!(f, "{}", crates.join(" "))?;
+        }
+        let numbers: Vec<_> = (1..=self.stacks.len())
+            .map(|i| format!(" {} ", i))
+            .collect();
+        write!(f, "{}", numbers.join(" "))
+    }
+}
+
+/// What it took a crane to carry out its moves
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Stats {
+    pub moves: usize,
+    pub crates: usize,
+    pub lifts: usize,
+}
+
+/// A model of crane, deciding how crates get from one stack to another
+trait Crane {
+    /// Carry out a move which has already been checked against the stacks,
+    /// returning how many lifts it took
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize;
+}
+
+/// Moves crates one at a time
+#[derive(Default)]
 struct Mover9000;
+
+/// Moves all the crates at once, so they keep their order
+#[derive(Default)]
 struct Mover9001;
 
-trait Restack<T> {
-    fn rearrange(&mut self, count: usize, from: usize, to: usize);
+/// Moves as many crates at once as it can manage, so big moves are split
+/// into several lifts from the top down
+struct Capped(pub usize);
+
+/// Moves all the crates at once, but its grabber is worn, so every other
+/// crate swaps places with the one below it on the way
+struct Swapping;
+
+/// Has a hook for every stack, so consecutive moves between different stacks
+/// all happen in the same lift.  A new lift starts whenever a move involves a
+/// stack the current one is already using.
+#[derive(Default)]
+struct Gantry {
+    busy: Vec<bool>,
+}
+
+impl Crane for Mover9000 {
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize {
+        Capped(1).rearrange(stacks, step)
+    }
+}
+
+impl Crane for Mover9001 {
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize {
+        Capped(usize::MAX).rearrange(stacks, step)
+    }
+}
+
+impl Crane for Capped {
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize {
+        let mut left = step.count;
+        let mut lifts = 0;
+        while left > 0 {
+            let size = left.min(self.0);
+            let from = &mut stacks[step.from];
+            let items: Vec<_> = from.drain(from.len() - size..).collect();
+            stacks[step.to].extend(items);
+            left -= size;
+            lifts += 1;
+        }
+        lifts
+    }
 }
 
-impl Restack<Mover9000> for Stacks {
-    fn rearrange(&mut self, count: usize, from: usize, to: usize) {
-        for _ in 0..count {
-            let item = self.stacks.get_mut(from).unwrap().pop().unwrap();
-            self.stacks.get_mut(to).unwrap().push(item);
+impl Crane for Swapping {
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize {
+        let from = &mut stacks[step.from];
+        let mut items: Vec<_> = from.drain(from.len() - step.count..).collect();
+        for pair in items.chunks_mut(2) {
+            pair.reverse();
         }
+        stacks[step.to].extend(items);
+        usize::from(step.count > 0)
+    }
+}
+
+impl Crane for Gantry {
+    fn rearrange(&mut self, stacks: &mut [Vec<char>], step: &Move) -> usize {
+        self.busy.resize(stacks.len(), false);
+        let lifts = match self.busy[step.from] || self.busy[step.to] {
+            true => {
+                self.busy.fill(false);
+                1
+            }
+            // The very first move also needs a lift of its own
+            false => usize::from(!self.busy.contains(&true)),
+        };
+        self.busy[step.from] = true;
+        self.busy[step.to] = true;
+        Mover9001.rearrange(stacks, step);
+        lifts
     }
 }
 
-impl Restack<Mover9001> for Stacks {
-    fn rearrange(&mut self, count: usize, from: usize, to: usize) {
-        let from = self.stacks.get_mut(from).unwrap();
-        let items: Vec<_> = from.drain(from.len() - count..).collect();
-        self.stacks.get_mut(to).unwrap().extend(items);
+/// Pick a crane model by name
+fn model(name: &str) -> Option<Box<dyn Crane>> {
+    match name.split_once(':') {
+        Some(("capped", n)) => n
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .map(|n| Box::new(Capped(n)) as _),
+        _ => match name {
+            "9000" => Some(Box::new(Mover9000)),
+            "9001" => Some(Box::new(Mover9001)),
+            "swapping" => Some(Box::new(Swapping)),
+            "gantry" => Some(Box::<Gantry>::default()),
+            _ => None,
+        },
     }
 }
 
+const CRANES: &str = "9000, 9001, capped:N, swapping or gantry";
+
 impl From<&mut Lines<'_>> for Stacks {
     fn from(lines: &mut Lines) -> Stacks {
         // Read everything - crates and the numbers below
         let mut stacks: Vec<Vec<char>> = Vec::new();
         for line in lines.take_while(|s| !s.is_empty()) {
-            let items = line.chars().skip(1).step_by(4).map(|c| c);
+            let items = line.chars().skip(1).step_by(4);
             if stacks.is_empty() {
                 stacks.extend(items.map(|c| vec![c]));
             } else {
@@ -57,32 +396,61 @@ impl From<&mut Lines<'_>> for Stacks {
 
         Stacks {
             stacks: stacks.collect(),
+            ..Default::default()
         }
     }
 }
 
-fn part<T>(input: &str) -> String
+/// Parse the moves following the drawing of the stacks, keeping track of
+/// which line each came from
+fn parse_moves(input: &str) -> Result<Vec<(usize, Move)>, CraneError> {
+    let mut lines = input.lines().zip(1..);
+    // Skip over the drawing, up to and including the blank line after it
+    lines.by_ref().find(|(s, _)| s.is_empty());
+    lines
+        .filter(|(s, _)| !s.trim().is_empty())
+        .map(|(s, line)| match s.parse() {
+            Ok(step) => Ok((line, step)),
+            Err(_) => Err(CraneError::Parse {
+                line,
+                text: String::from(s),
+            }),
+        })
+        .collect()
+}
+
+/// Parse the stacks and carry out all the moves, calling `trace` with the
+/// number of moves done so far and the stacks after each one.  Stops at the
+/// first move which can't be done.
+fn run<C, F>(input: &str, crane: &mut C, mut trace: F) -> Result<(Stacks, Stats), CraneError>
 where
-    Stacks: Restack<T>,
+    C: Crane + ?Sized,
+    F: FnMut(usize, &Stacks),
 {
-    let mut lines = input.lines();
-    let mut stacks = Stacks::from(&mut lines);
-
-    for line in lines {
-        let mut numbers = line
-            .split_whitespace()
-            .skip(1)
-            .step_by(2)
-            .map(|c| c.parse().unwrap());
-        Restack::<T>::rearrange(
-            &mut stacks,
-            numbers.next().unwrap(),
-            numbers.next().unwrap() - 1,
-            numbers.next().unwrap() - 1,
-        );
+    let mut stacks = Stacks::from(&mut input.lines());
+    let mut stats = Stats::default();
+    for ((line, step), i) in parse_moves(input)?.into_iter().zip(1..) {
+        stats.lifts += stacks
+            .rearrange(crane, &step)
+            .map_err(|e| CraneError::Line {
+                line,
+                error: Box::new(e),
+            })?;
+        stats.moves += 1;
+        stats.crates += step.count;
+        trace(i, &stacks);
     }
+    Ok((stacks, stats))
+}
 
-    stacks.tops()
+fn part<C>(input: &str) -> String
+where
+    C: Crane + Default,
+{
+    match run(input, &mut C::default(), |_, _| ()) {
+        Ok((stacks, _)) => stacks.tops(),
+        Err(e) => panic!("{}", e),
+    }
 }
 
 fn part1(input: &str) -> String {
@@ -93,6 +461,102 @@ fn part2(input: &str) -> String {
     part::<Mover9001>(input)
 }
 
+/// `crane <model>`: carry out the moves with a different model of crane
+pub fn crane(args: &[String]) {
+    let Some(mut crane) = args.first().and_then(|s| model(s)) else {
+        return println!("usage: crane <model>, where model is {}", CRANES);
+    };
+    match run(include_str!("input.txt"), crane.as_mut(), |_, _| ()) {
+        Ok((stacks, stats)) => {
+            println!("tops: {}", stacks.tops());
+            println!(
+                "moves: {}, crates: {}, lifts: {}",
+                stats.moves, stats.crates, stats.lifts
+            );
+        }
+        Err(e) => println!("failed at {}", e),
+    }
+}
+
+/// `trace [model] [every]`: draw the stacks after every few moves
+pub fn trace(args: &[String]) {
+    let usage = format!("usage: trace [model] [every], where model is {}", CRANES);
+    let Some(mut crane) = model(args.first().map_or("9000", |s| s.as_str())) else {
+        return println!("{}", usage);
+    };
+    let every = match args.get(1).map(|s| s.parse()) {
+        Some(Ok(n)) if n > 0 => n,
+        None => 1,
+        _ => return println!("{}", usage),
+    };
+
+    let input = include_str!("input.txt");
+    println!("{}\n", Stacks::from(&mut input.lines()));
+    let result = run(input, crane.as_mut(), |i, stacks| {
+        if i % every == 0 {
+            println!("after move {}:\n{}\n", i, stacks);
+        }
+    });
+    match result {
+        Ok((stacks, _)) => println!("final:\n{}", stacks),
+        Err(e) => println!("failed at {}", e),
+    }
+}
+
+/// `whatif <model> <checkpoint> [file]`: go back to a point in the moves and
+/// carry out the moves in `file` instead (or none at all), then compare the
+/// result with the real one
+pub fn whatif(args: &[String]) {
+    let usage = format!(
+        "usage: whatif <model> <checkpoint> [file], where model is {}",
+        CRANES
+    );
+    let (Some(mut crane), Some(Ok(checkpoint))) = (
+        args.first().and_then(|s| model(s)),
+        args.get(1).map(|s| s.parse()),
+    ) else {
+        return println!("{}", usage);
+    };
+    let moves = match args.get(2).map(std::fs::read_to_string) {
+        Some(Ok(text)) => {
+            let moves: Result<Vec<Move>, _> = text
+                .lines()
+                .zip(1..)
+                .filter(|(s, _)| !s.trim().is_empty())
+                .map(|(s, line)| {
+                    s.parse().map_err(|_| CraneError::Parse {
+                        line,
+                        text: String::from(s),
+                    })
+                })
+                .collect();
+            match moves {
+                Ok(moves) => moves,
+                Err(e) => return println!("could not parse moves: {}", e),
+            }
+        }
+        Some(Err(e)) => return println!("could not read moves: {}", e),
+        None => Vec::new(),
+    };
+
+    let mut stacks = match run(include_str!("input.txt"), crane.as_mut(), |_, _| ()) {
+        Ok((stacks, _)) => stacks,
+        Err(e) => return println!("failed at {}", e),
+    };
+    let real = Stacks {
+        stacks: stacks.stacks.clone(),
+        ..Default::default()
+    };
+
+    if let Err(e) = stacks.replay(checkpoint, crane.as_mut(), &moves) {
+        return println!("failed at {}", e);
+    }
+    println!("{}\n", stacks);
+    for difference in stacks.diff(&real) {
+        println!("{}", difference);
+    }
+}
+
 pub fn main() {
     let input = include_str!("input.txt");
     println!("part 1: {}", part1(input));
@@ -111,6 +575,174 @@ mod test {
         assert_eq!(Stacks::from(&mut INPUT.lines()).stacks, expected);
     }
 
+    #[test]
+    fn test_display() {
+        // Drawing the stacks should give back the original picture
+        let stacks = Stacks::from(&mut INPUT.lines());
+        let drawing: String = INPUT.lines().take(4).collect::<Vec<_>>().join("\n");
+        assert_eq!(stacks.to_string(), drawing);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut tops = Vec::new();
+        let (stacks, _) = run(INPUT, &mut Mover9000, |i, stacks| {
+            tops.push((i, stacks.tops()))
+        })
+        .unwrap();
+        assert_eq!(stacks.tops(), "CMZ");
+        assert_eq!(tops[0], (1, String::from("DCP")));
+        assert_eq!(tops[2], (3, String::from("M Z")));
+        assert_eq!(tops.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_moves() {
+        let moves = parse_moves(INPUT).unwrap();
+        let step = Move {
+            count: 1,
+            from: 1,
+            to: 0,
+        };
+        assert_eq!(moves[0], (6, step));
+        assert_eq!(step.to_string(), "move 1 from 2 to 1");
+
+        let input = "[A]\n 1 \n\nmove 1 from 1 to 1\nmove 1 from 0 to 1\n";
+        let error = CraneError::Parse {
+            line: 5,
+            text: String::from("move 1 from 0 to 1"),
+        };
+        assert_eq!(parse_moves(input), Err(error));
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let mut stacks = Stacks::from(&mut INPUT.lines());
+        let step = "move 3 from 3 to 1".parse().unwrap();
+        let error = CraneError::TooFew { step, available: 1 };
+        assert_eq!(stacks.rearrange(&mut Mover9001, &step), Err(error));
+
+        let step = "move 1 from 1 to 4".parse().unwrap();
+        let error = CraneError::NoStack { step, stack: 3 };
+        assert_eq!(stacks.rearrange(&mut Mover9000, &step), Err(error));
+
+        // Nothing should have changed
+        assert_eq!(stacks.tops(), "NDP");
+
+        let input = INPUT.replace("move 2 from 2 to 1", "move 4 from 2 to 1");
+        let error = run(&input, &mut Mover9000, |_, _| ()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 8: move 4 from 2 to 1: stack 2 only has 2 crates"
+        );
+    }
+
+    #[test]
+    fn test_cranes() {
+        let tops = |crane: &mut dyn Crane| {
+            let (stacks, stats) = run(INPUT, crane, |_, _| ()).unwrap();
+            (stacks.tops(), stats.lifts)
+        };
+        assert_eq!(tops(&mut Mover9000), (String::from("CMZ"), 7));
+        assert_eq!(tops(&mut Mover9001), (String::from("MCD"), 4));
+        assert_eq!(tops(&mut Capped(1)), tops(&mut Mover9000));
+        assert_eq!(tops(&mut Capped(3)), tops(&mut Mover9001));
+        assert_eq!(tops(&mut Capped(2)), (String::from("MCZ"), 5));
+    }
+
+    #[test]
+    fn test_capped() {
+        let mut stacks = vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]];
+        let step = "move 5 from 1 to 2".parse().unwrap();
+        assert_eq!(Capped(2).rearrange(&mut stacks, &step), 3);
+        assert_eq!(stacks[1], vec!['D', 'E', 'B', 'C', 'A']);
+    }
+
+    #[test]
+    fn test_swapping() {
+        let mut stacks = vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]];
+        let step = "move 5 from 1 to 2".parse().unwrap();
+        assert_eq!(Swapping.rearrange(&mut stacks, &step), 1);
+        assert_eq!(stacks[1], vec!['B', 'A', 'D', 'C', 'E']);
+    }
+
+    #[test]
+    fn test_gantry() {
+        let mut gantry = Gantry::default();
+        let mut stacks = vec![vec!['A', 'B'], vec![], vec!['C'], vec![]];
+        let lifts: Vec<_> = [
+            "move 1 from 1 to 2",
+            "move 1 from 3 to 4",
+            "move 1 from 1 to 3",
+        ]
+        .iter()
+        .map(|s| gantry.rearrange(&mut stacks, &s.parse().unwrap()))
+        .collect();
+        assert_eq!(lifts, vec![1, 0, 1]);
+        assert_eq!(stacks, vec![vec![], vec!['B'], vec!['A'], vec!['C']]);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut states = Vec::new();
+        let (mut stacks, _) = run(INPUT, &mut Mover9000, |_, stacks| {
+            states.push(stacks.stacks.clone())
+        })
+        .unwrap();
+        assert_eq!(stacks.checkpoint(), 4);
+
+        assert_eq!(stacks.undo(), Some("move 1 from 1 to 2".parse().unwrap()));
+        assert_eq!(stacks.stacks, states[2]);
+        stacks.seek(1);
+        assert_eq!(stacks.stacks, states[0]);
+        stacks.seek(0);
+        assert_eq!(stacks, Stacks::from(&mut INPUT.lines()));
+        assert_eq!(stacks.undo(), None);
+
+        stacks.seek(3);
+        assert_eq!(stacks.stacks, states[2]);
+        assert!(stacks.redo().is_some());
+        assert_eq!(stacks.redo(), None);
+        assert_eq!(stacks.tops(), "CMZ");
+    }
+
+    #[test]
+    fn test_replay() {
+        let (mut stacks, _) = run(INPUT, &mut Mover9001, |_, _| ()).unwrap();
+        let original = Stacks {
+            stacks: stacks.stacks.clone(),
+            ..Default::default()
+        };
+
+        let moves = ["move 1 from 3 to 2", "move 2 from 1 to 3"].map(|s| s.parse().unwrap());
+        assert_eq!(stacks.replay(1, &mut Mover9001, &moves), Ok(2));
+        assert_eq!(stacks.checkpoint(), 3);
+        assert_eq!(stacks.tops(), "ZPD");
+
+        // Anything undone before the replay is gone for good
+        assert_eq!(stacks.redo(), None);
+
+        let expected = vec![
+            Difference {
+                stack: 0,
+                ours: vec!['Z'],
+                theirs: vec!['M'],
+            },
+            Difference {
+                stack: 1,
+                ours: vec!['M', 'C', 'P'],
+                theirs: vec!['C'],
+            },
+            Difference {
+                stack: 2,
+                ours: vec!['N', 'D'],
+                theirs: vec!['P', 'Z', 'N', 'D'],
+            },
+        ];
+        assert_eq!(stacks.diff(&original), expected);
+        assert!(original.diff(&original).is_empty());
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1(INPUT), "CMZ");
@@ -1,5 +1,8 @@
 use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read};
+use std::time::Instant;
 
+/// The original detector, kept as a reference for the faster one below
 fn find_sequence(input: &str, length: usize) -> Option<usize> {
     let mut buffer: VecDeque<_> = VecDeque::new();
     let mut counts: HashMap<_, _> = HashMap::new();
@@ -18,12 +21,271 @@ fn find_sequence(input: &str, length: usize) -> Option<usize> {
     None
 }
 
+/// Keeps track of how many different bytes are in a sliding window, doing a
+/// constant amount of work per byte
+struct Detector {
+    length: usize,
+    counts: [usize; 256],
+    distinct: usize,
+    /// The window itself, as a ring buffer
+    window: Vec<u8>,
+    /// Where the next byte goes in the window
+    slot: usize,
+    full: bool,
+}
+
+impl Detector {
+    pub fn new(length: usize) -> Self {
+        assert!(length > 0, "markers must be at least one byte long");
+        Detector {
+            length,
+            counts: [0; 256],
+            distinct: 0,
+            window: vec![0; length],
+            slot: 0,
+            full: false,
+        }
+    }
+
+    /// Add the next byte, and say whether the last `length` are all different
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.full {
+            // Drop the byte falling out of the window
+            let old = self.window[self.slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.window[self.slot] = byte;
+        self.slot += 1;
+        if self.slot == self.length {
+            self.slot = 0;
+            self.full = true;
+        }
+
+        let new = byte as usize;
+        if self.counts[new] == 0 {
+            self.distinct += 1;
+        }
+        self.counts[new] += 1;
+        self.distinct == self.length
+    }
+}
+
+/// The number of bytes read up to the end of the first marker of the given
+/// length, if there is one
+fn find_marker(input: &[u8], length: usize) -> Option<usize> {
+    let mut detector = Detector::new(length);
+    input.iter().position(|b| detector.push(*b)).map(|i| i + 1)
+}
+
+/// As `find_marker`, but reading from a stream until a marker turns up
+fn find_marker_in<R: Read>(reader: R, length: usize) -> io::Result<Option<usize>> {
+    let mut reader = BufReader::new(reader);
+    let mut detector = Detector::new(length);
+    let mut offset = 0;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(None);
+        }
+        if let Some(i) = buffer.iter().position(|b| detector.push(*b)) {
+            return Ok(Some(offset + i + 1));
+        }
+        offset += buffer.len();
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+}
+
+/// For each byte in turn, the length of the longest run of all-different
+/// bytes ending there
+struct Runs<'a> {
+    bytes: std::slice::Iter<'a, u8>,
+    /// One more than where each byte value was last seen, or 0 if it hasn't
+    last: [usize; 256],
+    /// Where the current run started
+    start: usize,
+    seen: usize,
+}
+
+impl<'a> Runs<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Runs {
+            bytes: input.iter(),
+            last: [0; 256],
+            start: 0,
+            seen: 0,
+        }
+    }
+}
+
+impl Iterator for Runs<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = *self.bytes.next()? as usize;
+        // A repeat cuts the run short just after the previous copy
+        self.start = self.start.max(self.last[byte]);
+        self.seen += 1;
+        self.last[byte] = self.seen;
+        Some(self.seen - self.start)
+    }
+}
+
+/// A position where the last `length` bytes are all different.  `end` counts
+/// the bytes read up to and including the marker, like the puzzle answers.
+#[derive(Debug, PartialEq)]
+struct Marker {
+    end: usize,
+    length: usize,
+}
+
+/// Every marker for any of the given lengths, found in a single pass.  They
+/// come in order of position, and then of length.
+struct Markers<'a> {
+    runs: Runs<'a>,
+    /// Sorted, so the ones which fit in the current run come first
+    lengths: Vec<usize>,
+    end: usize,
+    run: usize,
+    /// How many markers have been given for the current byte
+    given: usize,
+}
+
+impl<'a> Markers<'a> {
+    fn new(input: &'a [u8], lengths: &[usize]) -> Self {
+        assert!(
+            !lengths.contains(&0),
+            "markers must be at least one byte long"
+        );
+        let mut lengths = lengths.to_vec();
+        lengths.sort();
+        lengths.dedup();
+        Markers {
+            runs: Runs::new(input),
+            lengths,
+            end: 0,
+            run: 0,
+            given: 0,
+        }
+    }
+}
+
+impl Iterator for Markers<'_> {
+    type Item = Marker;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(length) = self.lengths.get(self.given).filter(|l| **l <= self.run) {
+                self.given += 1;
+                return Some(Marker {
+                    end: self.end,
+                    length: *length,
+                });
+            }
+            self.run = self.runs.next()?;
+            self.end += 1;
+            self.given = 0;
+        }
+    }
+}
+
+/// The longest run of all-different bytes, as (start, length)
+fn longest_run(input: &[u8]) -> Option<(usize, usize)> {
+    Runs::new(input)
+        .zip(1..)
+        .max_by_key(|(run, end)| (*run, usize::MAX - end))
+        .map(|(run, end)| (end - run, run))
+}
+
 fn part1(input: &str) -> usize {
-    find_sequence(input, 4).unwrap()
+    find_marker(input.as_bytes(), 4).unwrap()
 }
 
 fn part2(input: &str) -> usize {
-    find_sequence(input, 14).unwrap()
+    find_marker(input.as_bytes(), 14).unwrap()
+}
+
+/// `markers [length...]`: look for markers of several lengths at once (4 and
+/// 14 by default), and the longest stretch without repeats
+pub fn markers(args: &[String]) {
+    let Ok(mut lengths) = args
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<usize>, _>>()
+    else {
+        return println!("usage: markers [length...]");
+    };
+    if lengths.contains(&0) {
+        return println!("markers must be at least one byte long");
+    }
+    if lengths.is_empty() {
+        lengths = vec![4, 14];
+    }
+    // Keep the order asked for, but each length only once
+    let mut seen = vec![];
+    lengths.retain(|length| {
+        let new = !seen.contains(length);
+        seen.push(*length);
+        new
+    });
+
+    let input = include_str!("input.txt").trim_end().as_bytes();
+    let mut first = vec![None; lengths.len()];
+    let mut counts = vec![0; lengths.len()];
+    for marker in Markers::new(input, &lengths) {
+        for (i, length) in lengths.iter().enumerate() {
+            if *length == marker.length {
+                first[i] = first[i].or(Some(marker.end));
+                counts[i] += 1;
+            }
+        }
+    }
+    for ((length, first), count) in lengths.iter().zip(first).zip(counts) {
+        match first {
+            Some(end) => println!("length {}: first at {}, {} in total", length, end, count),
+            None => println!("length {}: none", length),
+        }
+    }
+    if let Some((start, length)) = longest_run(input) {
+        let run = String::from_utf8_lossy(&input[start..start + length]);
+        println!("longest run: {} bytes from {}: {}", length, start + 1, run);
+    }
+}
+
+/// `bench [megabytes]`: time the detectors on a generated stream whose only
+/// start-of-message marker is at the very end
+pub fn bench(args: &[String]) {
+    let megabytes = match args.first().map(|s| s.parse()) {
+        Some(Ok(n)) if n > 0 => n,
+        None => 4,
+        _ => return println!("usage: bench [megabytes]"),
+    };
+
+    // Cycle through 7 letters, so there's never 14 different in a row until
+    // the 7th of the new letters on the end
+    let mut input: String = "abcdefg".chars().cycle().take(megabytes << 20).collect();
+    input.push_str("hijklmnopqrstu");
+    let expected = Some((megabytes << 20) + 7);
+
+    let time = |name: &str, func: &dyn Fn() -> Option<usize>| {
+        let start = Instant::now();
+        assert_eq!(func(), expected);
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{:<16} {:>8.3}s {:>10.1} MB/s",
+            name,
+            seconds,
+            megabytes as f64 / seconds
+        );
+    };
+    time("HashMap", &|| find_sequence(&input, 14));
+    time("counts on slice", &|| find_marker(input.as_bytes(), 14));
+    time("counts on stream", &|| {
+        find_marker_in(input.as_bytes(), 14).unwrap()
+    });
 }
 
 pub fn main() {
@@ -47,4 +309,68 @@ mod test {
     fn test_part2() {
         assert_eq!(part2(INPUT), 19);
     }
+
+    #[test]
+    fn test_against_reference() {
+        let examples = [
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+            "aaaaaaaaaa",
+            include_str!("input.txt"),
+        ];
+        for input in examples {
+            for length in [1, 4, 14] {
+                let expected = find_sequence(input, length);
+                assert_eq!(find_marker(input.as_bytes(), length), expected);
+                assert_eq!(find_marker_in(input.as_bytes(), length).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_markers() {
+        let input = INPUT.as_bytes();
+        let found: Vec<_> = Markers::new(input, &[14, 4]).collect();
+        assert_eq!(found[0], Marker { end: 7, length: 4 });
+        let first = found.iter().find(|m| m.length == 14);
+        assert_eq!(
+            first,
+            Some(&Marker {
+                end: 19,
+                length: 14
+            })
+        );
+
+        // Every marker should agree with a direct check of the window
+        for marker in found {
+            let window = &input[marker.end - marker.length..marker.end];
+            assert_eq!(find_marker(window, marker.length), Some(marker.length));
+        }
+        let count = (4..=input.len())
+            .filter(|end| find_marker(&input[end - 4..*end], 4).is_some())
+            .count();
+        assert_eq!(Markers::new(input, &[4]).count(), count);
+        assert_eq!(Markers::new(input, &[4, 4]).count(), count);
+    }
+
+    #[test]
+    fn test_longest_run() {
+        assert_eq!(longest_run(b""), None);
+        assert_eq!(longest_run(b"aaaa"), Some((0, 1)));
+        assert_eq!(longest_run(b"abcabcdab"), Some((3, 4)));
+        assert_eq!(
+            longest_run(INPUT.trim_end().as_bytes()).map(|r| r.1),
+            Some(18)
+        );
+    }
+
+    #[test]
+    fn test_stream_across_buffers() {
+        // Put the marker well past the end of the first buffer full
+        let mut input = vec![b'a'; 20000];
+        input.extend(b"abcd");
+        assert_eq!(find_marker_in(&input[..], 4).unwrap(), Some(20004));
+    }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{Tree, ROOT};
+
+/// Recreate the tree under `path`, which mustn't exist yet.  Files are made by
+/// setting their length, so they take up next to no space on filesystems
+/// which support sparse files.
+pub fn materialise(tree: &Tree, path: &Path) -> io::Result<()> {
+    fs::create_dir(path)?;
+    for id in tree.walk(ROOT).skip(1) {
+        // Paths from the tree are absolute, so make them relative first
+        let target = path.join(&tree.path(id)[1..]);
+        if tree[id].is_dir() {
+            fs::create_dir(&target)?;
+        } else {
+            fs::File::create(&target)?.set_len(tree[id].size as u64)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run `du` over a tree made by `materialise` at `path`, and list every file
+/// whose size doesn't match the tree's.  `du` counts the space taken by each
+/// directory itself as well as the files in it, so those are taken off its
+/// totals first.  Only GNU `du` knows `-b`, so anywhere else this fails with
+/// `ErrorKind::Unsupported`.
+pub fn check_du(tree: &Tree, path: &Path) -> io::Result<Vec<String>> {
+    let output = Command::new("du").args(["-a", "-b"]).arg(path).output()?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("du failed: {}", error.trim()),
+        ));
+    }
+    let reported: HashMap<PathBuf, usize> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (size, file) = line.split_once('\t')?;
+            Some((PathBuf::from(file), size.parse().ok()?))
+        })
+        .collect();
+
+    let mut mismatches = vec![];
+    for id in tree.walk(ROOT) {
+        let target = path.join(&tree.path(id)[1..]);
+        let mut overhead = 0;
+        for dir in tree.walk(id).filter(|d| tree[*d].is_dir()) {
+            overhead += fs::metadata(path.join(&tree.path(dir)[1..]))?.len() as usize;
+        }
+        // `join` with an empty path leaves a trailing slash, which `du` doesn't
+        let key = if id == ROOT {
+            path.to_path_buf()
+        } else {
+            target
+        };
+        // Something may have changed on disk since `du` looked at it
+        match reported.get(&key).map(|size| size.checked_sub(overhead)) {
+            Some(Some(size)) if size == tree[id].size => (),
+            Some(Some(size)) => mismatches.push(format!(
+                "{}: {} but du says {}",
+                tree.path(id),
+                tree[id].size,
+                size
+            )),
+            Some(None) => mismatches.push(format!(
+                "{}: du says less than its directories take up",
+                tree.path(id)
+            )),
+            None => mismatches.push(format!("{}: missing from du", tree.path(id))),
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Write out the `cd` and `ls` commands which would explore a real directory,
+/// starting from `/` at `path`.  Anything but plain files and directories is
+/// left out, so that symlinks can't lead anywhere unexpected.
+pub fn transcript(path: &Path) -> io::Result<String> {
+    let mut out = String::from("$ cd /\n");
+    explore(path, &mut out)?;
+    Ok(out)
+}
+
+fn explore(path: &Path, out: &mut String) -> io::Result<()> {
+    let mut files = vec![];
+    let mut dirs = vec![];
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|name| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad name {:?}", name))
+        })?;
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            dirs.push(name);
+        } else if kind.is_file() {
+            files.push((name, entry.metadata()?.len()));
+        }
+    }
+    dirs.sort();
+    files.sort();
+
+    out.push_str("$ ls\n");
+    for name in &dirs {
+        out.push_str(&format!("dir {}\n", name));
+    }
+    for (name, size) in &files {
+        out.push_str(&format!("{} {}\n", size, name));
+    }
+    for name in &dirs {
+        out.push_str(&format!("$ cd {}\n", name));
+        explore(&path.join(name), out)?;
+        out.push_str("$ cd ..\n");
+    }
+    Ok(())
+}
+
+/// Somewhere new to put a tree, under the system's temporary directory
+pub fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("aoc-day07-{}-{}", std::process::id(), name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse_input;
+    use super::*;
+    use std::io::ErrorKind;
+
+    const INPUT: &str = include_str!("test.txt");
+
+    /// Every file in a tree as (path, size, is directory)
+    fn summary(tree: &Tree) -> Vec<(String, usize, bool)> {
+        tree.walk(ROOT)
+            .map(|id| (tree.path(id), tree[id].size, tree[id].is_dir()))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tree = parse_input(INPUT).unwrap();
+        let path = temp_path("round-trip");
+        materialise(&tree, &path).unwrap();
+        let size = fs::metadata(path.join("d/d.log")).unwrap().len();
+        let text = transcript(&path);
+        // Can't put the same tree in the same place twice
+        let again = materialise(&tree, &path);
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(size, 8033020);
+        assert!(again.is_err());
+        let text = text.unwrap();
+        assert!(text.starts_with("$ cd /\n$ ls\ndir a\ndir d\n14848514 b.txt\n"));
+        assert_eq!(summary(&parse_input(&text).unwrap()), summary(&tree));
+    }
+
+    #[test]
+    fn test_check_du() {
+        let tree = parse_input(INPUT).unwrap();
+        let path = temp_path("check-du");
+        materialise(&tree, &path).unwrap();
+        let agreed = check_du(&tree, &path);
+        // Make a file bigger behind the tree's back
+        fs::File::options()
+            .write(true)
+            .open(path.join("a/e/i"))
+            .unwrap()
+            .set_len(600)
+            .unwrap();
+        let changed = check_du(&tree, &path);
+        fs::remove_dir_all(&path).unwrap();
+
+        match agreed {
+            // Nothing to compare against without a GNU `du`
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::Unsupported) => return,
+            agreed => assert_eq!(agreed.unwrap(), Vec::<String>::new()),
+        }
+        let changed = changed.unwrap();
+        assert_eq!(changed[0], "/: 48381165 but du says 48381181");
+        assert_eq!(changed.len(), 4);
+        assert!(changed.contains(&String::from("/a/e/i: 584 but du says 600")));
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        assert!(transcript(&temp_path("missing")).is_err());
+    }
+}
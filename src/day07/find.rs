@@ -0,0 +1,150 @@
+use super::{Tree, ROOT};
+
+/// What to look for, like the tests given to `find`.  Anything left as `None`
+/// matches every file.
+#[derive(Debug, Default)]
+pub struct Query {
+    /// A glob for the name, with `*`, `?` and `[...]`
+    pub name: Option<String>,
+    /// What comes after the last `.` in the name
+    pub extension: Option<String>,
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    /// How far below the root, which is at depth 0
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// Only directories, or only plain files
+    pub dir: Option<bool>,
+}
+
+/// The full paths of every file matching the query, in the order `walk` finds
+/// them
+pub fn find(tree: &Tree, query: &Query) -> Vec<String> {
+    let depths = tree.depths();
+    tree.walk(ROOT)
+        .filter(|id| {
+            let file = &tree[*id];
+            let depth = depths[*id];
+            query.name.as_ref().is_none_or(|g| glob(g, &file.name))
+                && query
+                    .extension
+                    .as_ref()
+                    .is_none_or(|e| file.name.rsplit_once('.').is_some_and(|(_, ext)| ext == e))
+                && query.min_size.is_none_or(|min| file.size >= min)
+                && query.max_size.is_none_or(|max| file.size <= max)
+                && query.min_depth.is_none_or(|min| depth >= min)
+                && query.max_depth.is_none_or(|max| depth <= max)
+                && query.dir.is_none_or(|dir| file.is_dir() == dir)
+        })
+        .map(|id| tree.path(id))
+        .collect()
+}
+
+/// Whether a name matches a shell-style pattern
+pub fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    glob_chars(&pattern, &name)
+}
+
+fn glob_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| glob_chars(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && glob_chars(&pattern[1..], &name[1..]),
+        // A `]` straight after the `[` is part of the class, as in the shell
+        Some('[') => match pattern.iter().skip(2).position(|c| *c == ']') {
+            Some(end) => {
+                let class = &pattern[1..end + 2];
+                name.first().is_some_and(|c| in_class(class, *c))
+                    && glob_chars(&pattern[end + 3..], &name[1..])
+            }
+            None => name.first() == Some(&'[') && glob_chars(&pattern[1..], &name[1..]),
+        },
+        Some(c) => name.first() == Some(c) && glob_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Whether a character is in a class like `abc`, `a-z` or `!0-9`
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') if class.len() > 1 => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse_input;
+    use super::*;
+
+    const INPUT: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("*", ""));
+        assert!(glob("*.txt", "b.txt"));
+        assert!(!glob("*.txt", "b.txt.gz"));
+        assert!(glob("d.*", "d.log"));
+        assert!(glob("?", "k"));
+        assert!(!glob("?", "kk"));
+        assert!(glob("[a-f]", "c"));
+        assert!(!glob("[!a-f]", "c"));
+        assert!(glob("[]x]", "]"));
+        assert!(glob("a[", "a["));
+        assert!(glob("*a*b*c", "xaxxbxxxc"));
+        assert!(!glob("*a*b*c", "xaxxbxxx"));
+    }
+
+    #[test]
+    fn test_find() {
+        let tree = parse_input(INPUT).unwrap();
+        let find = |query| find(&tree, &query);
+        let everything = find(Query::default());
+        assert_eq!(everything.len(), 14);
+        assert_eq!(everything[0], "/");
+
+        let query = Query {
+            name: Some(String::from("d*")),
+            ..Default::default()
+        };
+        assert_eq!(find(query), ["/d", "/d/d.ext", "/d/d.log"]);
+        let query = Query {
+            extension: Some(String::from("lst")),
+            ..Default::default()
+        };
+        assert_eq!(find(query), ["/a/h.lst"]);
+        let query = Query {
+            min_size: Some(5000000),
+            max_size: Some(10000000),
+            dir: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(find(query), ["/c.dat", "/d/d.ext", "/d/d.log", "/d/k"]);
+        let query = Query {
+            min_depth: Some(2),
+            max_depth: Some(2),
+            dir: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(find(query), ["/a/e"]);
+        let query = Query {
+            name: Some(String::from("[a-c]*")),
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(find(query), ["/a", "/b.txt", "/c.dat"]);
+    }
+}
@@ -1,15 +1,107 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Index;
 use std::str::FromStr;
 
+mod disk;
+mod find;
+mod report;
+
+use find::Query;
+
+use report::{parse_size, render_du, render_tree, View};
+
 #[derive(Debug)]
 struct ParseError;
 
+/// Something in a transcript which can't have happened
+#[derive(Debug, PartialEq)]
+enum ShellError {
+    /// A line which is neither a command we know nor output we expect
+    Parse(String),
+    /// A path which doesn't lead anywhere
+    NotFound(String),
+    /// A path which leads to a file where a directory is needed
+    NotADir(String),
+    /// A directory given to `rm` without `-r`
+    IsADir(String),
+    /// A path which already leads somewhere
+    Exists(String),
+    /// A path to the root or the current directory, which can't be removed
+    Busy(String),
+    /// A file which was listed differently before
+    Contradiction { path: String, listed: String },
+    /// A file which was missing when its directory was listed
+    Unlisted(String),
+    /// `pwd` printing somewhere other than the current directory
+    Pwd { cwd: String, printed: String },
+    /// Any of the above, along with the line of the transcript it came from
+    Line { line: usize, error: Box<ShellError> },
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShellError::Parse(text) => write!(f, "unexpected {:?}", text),
+            ShellError::NotFound(path) => write!(f, "{}: no such file or directory", path),
+            ShellError::NotADir(path) => write!(f, "{}: not a directory", path),
+            ShellError::IsADir(path) => write!(f, "{}: is a directory", path),
+            ShellError::Exists(path) => write!(f, "{}: already exists", path),
+            ShellError::Busy(path) => write!(f, "{}: cannot remove", path),
+            ShellError::Contradiction { path, listed } => {
+                write!(
+                    f,
+                    "{} listed as {:?}, which contradicts before",
+                    path, listed
+                )
+            }
+            ShellError::Unlisted(path) => write!(f, "{} missing from listing", path),
+            ShellError::Pwd { cwd, printed } => {
+                write!(f, "pwd printed {} but should be in {}", printed, cwd)
+            }
+            ShellError::Line { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum Cmd {
-    CdRoot,
-    CdUp,
+    /// Any path, relative or absolute
     Cd(String),
-    Ls,
+    /// The current directory, or another one
+    Ls(Option<String>),
+    /// With `-p`, any missing parents are made too
+    Mkdir {
+        paths: Vec<String>,
+        parents: bool,
+    },
+    /// With `-r`, whole directories can go, and with `-f` missing files are
+    /// fine
+    Rm {
+        paths: Vec<String>,
+        recursive: bool,
+        force: bool,
+    },
+    Pwd,
+}
+
+/// Split a command's arguments into the letters of the flags given and the
+/// paths, failing on any flag which isn't allowed
+fn flags_and_paths(args: &str, allowed: &str) -> Result<(String, Vec<String>), ParseError> {
+    let mut flags = String::new();
+    let mut paths = Vec::new();
+    for arg in args.split_whitespace() {
+        match arg.strip_prefix('-') {
+            Some(letters)
+                if !letters.is_empty() && letters.chars().all(|c| allowed.contains(c)) =>
+            {
+                flags.push_str(letters)
+            }
+            Some(_) => return Err(ParseError),
+            None => paths.push(String::from(arg)),
+        }
+    }
+    Ok((flags, paths))
 }
 
 impl FromStr for Cmd {
@@ -17,32 +109,75 @@ impl FromStr for Cmd {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let command = s.strip_prefix("$ ").ok_or(ParseError)?;
-        match command.split_once(' ').unwrap_or_else(|| (command, "")) {
-            ("cd", "/") => Ok(Cmd::CdRoot),
-            ("cd", "..") => Ok(Cmd::CdUp),
-            ("cd", args) if args.len() > 0 => Ok(Cmd::Cd(String::from(args))),
-            ("ls", "") => Ok(Cmd::Ls),
+        let (name, args) = command.split_once(' ').unwrap_or((command, ""));
+        match name {
+            "cd" if !args.is_empty() => Ok(Cmd::Cd(String::from(args))),
+            "ls" if args.is_empty() => Ok(Cmd::Ls(None)),
+            // No flags for `ls`, since they'd change what the output looks like
+            "ls" if !args.starts_with('-') => Ok(Cmd::Ls(Some(String::from(args)))),
+            "mkdir" => match flags_and_paths(args, "p")? {
+                (_, paths) if paths.is_empty() => Err(ParseError),
+                (flags, paths) => Ok(Cmd::Mkdir {
+                    paths,
+                    parents: !flags.is_empty(),
+                }),
+            },
+            "rm" => match flags_and_paths(args, "rRf")? {
+                (_, paths) if paths.is_empty() => Err(ParseError),
+                (flags, paths) => Ok(Cmd::Rm {
+                    paths,
+                    recursive: flags.contains(['r', 'R']),
+                    force: flags.contains('f'),
+                }),
+            },
+            "pwd" if args.is_empty() => Ok(Cmd::Pwd),
             _ => Err(ParseError),
         }
     }
 }
 
+/// Where a file lives in its `Tree`
+type Id = usize;
+
+const ROOT: Id = 0;
+
 #[derive(Debug)]
 struct File {
     name: String,
+    /// For directories, the total size of everything inside
     size: usize,
+    parent: Option<Id>,
     contents: Option<Listing>,
 }
 
+/// The files in a directory, by name
 #[derive(Debug, Default)]
-struct Listing(HashMap<String, File>);
+struct Listing(BTreeMap<String, Id>);
 
 impl File {
-    pub fn calc_size(&mut self) -> usize {
-        if let Some(listing) = &mut self.contents {
-            self.size = listing.0.values_mut().fold(0, |s, f| s + f.calc_size());
+    /// A new, empty directory
+    pub fn dir(name: &str) -> Self {
+        File {
+            name: String::from(name),
+            size: 0,
+            parent: None,
+            contents: Some(Default::default()),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.contents.is_some()
+    }
+}
+
+/// The way `ls` shows a file
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dir() {
+            write!(f, "dir {}", self.name)
+        } else {
+            write!(f, "{} {}", self.size, self.name)
         }
-        self.size
     }
 }
 
@@ -51,6 +186,10 @@ impl FromStr for File {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (ft, name) = s.split_once(' ').ok_or(ParseError)?;
+        // Anything else couldn't be a name in a real filesystem
+        if matches!(name, "" | "." | "..") || name.contains('/') {
+            return Err(ParseError);
+        }
 
         let (size, contents) = match ft.parse() {
             Ok(n) => (n, None),
@@ -61,75 +200,547 @@ impl FromStr for File {
         Ok(File {
             name: String::from(name),
             size,
+            parent: None,
             contents,
         })
     }
 }
 
-fn parse_input(input: &str) -> File {
-    let mut prev_cmd = None;
-    let mut root = File {
-        name: String::from(""),
-        size: 0,
-        contents: Some(Default::default()),
-    };
+/// A filesystem, with every file kept in one arena and referring to the
+/// others by index, so that moving up and down is cheap in both directions
+#[derive(Debug)]
+struct Tree {
+    files: Vec<File>,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Tree {
+            files: vec![File::dir("")],
+        }
+    }
+}
+
+impl Index<Id> for Tree {
+    type Output = File;
+
+    fn index(&self, id: Id) -> &Self::Output {
+        &self.files[id]
+    }
+}
+
+impl Tree {
+    /// Put a file in the given directory, and return where it ended up.  It's
+    /// fine for it to be there already, as long as it hasn't changed.
+    pub fn insert(&mut self, dir: Id, mut file: File) -> Result<Id, ShellError> {
+        if let Some(id) = self.child(dir, &file.name) {
+            let known = &self.files[id];
+            return match (known.is_dir(), file.is_dir()) {
+                (true, true) => Ok(id),
+                (false, false) if known.size == file.size => Ok(id),
+                _ => Err(ShellError::Contradiction {
+                    path: self.path(id),
+                    listed: file.to_string(),
+                }),
+            };
+        }
+        let id = self.files.len();
+        let listing = self.files[dir].contents.as_mut().expect("not a directory");
+        listing.0.insert(file.name.clone(), id);
+        file.parent = Some(dir);
+        self.files.push(file);
+        Ok(id)
+    }
+
+    /// Follow a path which should end at a directory
+    pub fn cd(&self, from: Id, path: &str) -> Result<Id, ShellError> {
+        match self.resolve(from, path) {
+            Some(id) if self.files[id].is_dir() => Ok(id),
+            Some(_) => Err(ShellError::NotADir(String::from(path))),
+            None => Err(ShellError::NotFound(String::from(path))),
+        }
+    }
+
+    /// Make a new, empty directory, in a directory which already exists
+    pub fn mkdir(&mut self, from: Id, path: &str) -> Result<Id, ShellError> {
+        let trimmed = path.trim_end_matches('/');
+        let (dir, name) = match trimmed.rsplit_once('/') {
+            Some(("", name)) => (ROOT, name),
+            Some((parent, name)) => (self.cd(from, parent)?, name),
+            None => (from, trimmed),
+        };
+        if matches!(name, "" | "." | "..") || self.child(dir, name).is_some() {
+            return Err(ShellError::Exists(String::from(path)));
+        }
+        self.insert(dir, File::dir(name))
+    }
+
+    /// Make a directory along with any missing parents, like `mkdir -p`.  Any
+    /// or all of them can be there already.
+    pub fn mkdir_all(&mut self, from: Id, path: &str) -> Result<Id, ShellError> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(start, |dir, name| match name {
+                "." => Ok(dir),
+                ".." => self
+                    .parent(dir)
+                    .ok_or_else(|| ShellError::NotFound(String::from(path))),
+                _ => match self.child(dir, name) {
+                    Some(id) if self.files[id].is_dir() => Ok(id),
+                    Some(_) => Err(ShellError::NotADir(String::from(path))),
+                    None => self.insert(dir, File::dir(name)),
+                },
+            })
+    }
 
-    // Store a list of all directory names so far, and the current directory.
-    // Ideally, this would just be a list of directories, but they all need
-    // to be &mut, but then we can't do anything else with them...  Could get
-    // around it with Rc<RefCell<File>> but that seems overkill.
-    let mut dirs: Vec<String> = vec![];
-    let mut cwd = &mut root;
-
-    for line in input.lines() {
-        if let Ok(cmd) = Cmd::from_str(line) {
-            match cmd {
-                Cmd::CdRoot => {
-                    dirs.clear();
-                    cwd = &mut root;
+    /// Take a file out of the tree, or a whole directory if `recursive`.  It
+    /// stays in the arena, but can't be reached any more.
+    pub fn remove(&mut self, cwd: Id, path: &str, recursive: bool) -> Result<(), ShellError> {
+        let id = self
+            .resolve(cwd, path)
+            .ok_or_else(|| ShellError::NotFound(String::from(path)))?;
+        if self.files[id].is_dir() && !recursive {
+            return Err(ShellError::IsADir(String::from(path)));
+        }
+        let mut dir = Some(cwd);
+        while let Some(current) = dir {
+            if current == id {
+                return Err(ShellError::Busy(String::from(path)));
+            }
+            dir = self.parent(current);
+        }
+
+        // Anything but the root has a parent, and the root was caught above
+        let parent = self.files[id].parent.take().unwrap();
+        let name = self.files[id].name.clone();
+        let listing = self.files[parent].contents.as_mut().unwrap();
+        listing.0.remove(&name);
+        Ok(())
+    }
+
+    pub fn parent(&self, id: Id) -> Option<Id> {
+        self.files[id].parent
+    }
+
+    pub fn child(&self, dir: Id, name: &str) -> Option<Id> {
+        self.files[dir].contents.as_ref()?.0.get(name).copied()
+    }
+
+    /// The files directly inside a directory, in order of name
+    pub fn children(&self, dir: Id) -> impl Iterator<Item = Id> + '_ {
+        self.files[dir]
+            .contents
+            .iter()
+            .flat_map(|listing| listing.0.values().copied())
+    }
+
+    /// A file and everything below it, depth first with each directory
+    /// before its contents
+    pub fn walk(&self, id: Id) -> Walk<'_> {
+        Walk {
+            tree: self,
+            stack: vec![id],
+        }
+    }
+
+    /// Every directory, starting with the root
+    pub fn dirs(&self) -> impl Iterator<Item = Id> + '_ {
+        self.walk(ROOT).filter(|id| self.files[*id].is_dir())
+    }
+
+    /// Follow a path from the given directory.  Absolute paths start from the
+    /// root instead, and `.` and `..` work as usual.
+    pub fn resolve(&self, from: Id, path: &str) -> Option<Id> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(start, |id, name| match name {
+                "." => Some(id),
+                ".." => self.parent(id),
+                _ => self.child(id, name),
+            })
+    }
+
+    /// The absolute path of a file
+    pub fn path(&self, id: Id) -> String {
+        let mut names = vec![];
+        let mut current = Some(id);
+        while let Some(id) = current.filter(|id| *id != ROOT) {
+            names.push(self.files[id].name.as_str());
+            current = self.parent(id);
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// How far below the root each file is
+    pub fn depths(&self) -> Vec<usize> {
+        // Parents always come before their contents
+        let mut depths = vec![0; self.files.len()];
+        for id in 1..self.files.len() {
+            if let Some(parent) = self.parent(id) {
+                depths[id] = depths[parent] + 1;
+            }
+        }
+        depths
+    }
+
+    /// Work out the total size of every directory
+    pub fn calc_sizes(&mut self) {
+        // Files are only ever added after their parents, so going backwards
+        // deals with everything inside a directory before the directory
+        for id in (0..self.files.len()).rev() {
+            if self.files[id].is_dir() {
+                self.files[id].size = self.children(id).map(|c| self.files[c].size).sum();
+            }
+        }
+    }
+}
+
+/// Iterator for `Tree::walk`
+struct Walk<'a> {
+    tree: &'a Tree,
+    stack: Vec<Id>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.stack.pop()?;
+        // Reversed, so the first child comes off the stack first
+        let start = self.stack.len();
+        self.stack.extend(self.tree.children(id));
+        self.stack[start..].reverse();
+        Some(id)
+    }
+}
+
+/// What the lines after a command should be
+enum Output {
+    Nothing,
+    /// The contents of a directory, along with the names seen so far
+    Listing(Id, BTreeSet<String>),
+    Pwd,
+}
+
+/// Follows a transcript along, building up the tree as it goes
+struct Shell {
+    tree: Tree,
+    cwd: Id,
+    output: Output,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        Shell {
+            tree: Tree::default(),
+            cwd: ROOT,
+            output: Output::Nothing,
+        }
+    }
+
+    pub fn run(&mut self, cmd: Cmd) -> Result<(), ShellError> {
+        self.finish()?;
+        match cmd {
+            Cmd::Cd(path) => self.cwd = self.tree.cd(self.cwd, &path)?,
+            Cmd::Ls(path) => {
+                let dir = match path {
+                    Some(path) => self.tree.cd(self.cwd, &path)?,
+                    None => self.cwd,
+                };
+                self.output = Output::Listing(dir, BTreeSet::new());
+            }
+            Cmd::Mkdir { paths, parents } => {
+                for path in paths {
+                    if parents {
+                        self.tree.mkdir_all(self.cwd, &path)?;
+                    } else {
+                        self.tree.mkdir(self.cwd, &path)?;
+                    }
                 }
-                Cmd::CdUp => {
-                    dirs.pop().unwrap();
-                    // Cd back into the right directory starting from root
-                    cwd = &mut root;
-                    for name in dirs.iter() {
-                        cwd = cwd.contents.as_mut().unwrap().0.get_mut(name).unwrap();
+            }
+            Cmd::Rm {
+                paths,
+                recursive,
+                force,
+            } => {
+                for path in paths {
+                    match self.tree.remove(self.cwd, &path, recursive) {
+                        Err(ShellError::NotFound(_)) if force => (),
+                        result => result?,
                     }
                 }
-                Cmd::Cd(ref name) => {
-                    dirs.push(name.clone());
-                    let listing = match cwd.contents {
-                        Some(ref mut listing) => listing,
-                        None => panic!("cannot cd"),
-                    };
-                    cwd = listing.0.get_mut(name).unwrap();
+            }
+            Cmd::Pwd => self.output = Output::Pwd,
+        }
+        Ok(())
+    }
+
+    /// Check a line of output against what we know
+    pub fn output(&mut self, text: &str) -> Result<(), ShellError> {
+        match &mut self.output {
+            Output::Listing(dir, names) => {
+                let file =
+                    File::from_str(text).map_err(|_| ShellError::Parse(String::from(text)))?;
+                names.insert(file.name.clone());
+                self.tree.insert(*dir, file)?;
+            }
+            Output::Pwd => {
+                let cwd = self.tree.path(self.cwd);
+                if text != cwd {
+                    let printed = String::from(text);
+                    return Err(ShellError::Pwd { cwd, printed });
                 }
-                _ => (),
-            };
-            prev_cmd = Some(cmd);
-        } else if prev_cmd == Some(Cmd::Ls) {
-            let file = File::from_str(line).expect("expected file listing");
-            cwd.contents
-                .as_mut()
-                .unwrap()
-                .0
-                .insert(file.name.clone(), file);
+                self.output = Output::Nothing;
+            }
+            Output::Nothing => return Err(ShellError::Parse(String::from(text))),
         }
+        Ok(())
     }
 
-    root.calc_size();
+    /// Wrap up the last command.  A listing has to mention everything already
+    /// known to be in the directory.
+    pub fn finish(&mut self) -> Result<(), ShellError> {
+        if let Output::Listing(dir, names) = std::mem::replace(&mut self.output, Output::Nothing) {
+            let tree = &self.tree;
+            if let Some(id) = tree
+                .children(dir)
+                .find(|id| !names.contains(&tree[*id].name))
+            {
+                return Err(ShellError::Unlisted(tree.path(id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_input(input: &str) -> Result<Tree, ShellError> {
+    let mut shell = Shell::new();
+    let mut last = 0;
+    for (text, line) in input.lines().zip(1..) {
+        let result = match Cmd::from_str(text) {
+            Ok(cmd) => shell.run(cmd),
+            Err(_) if text.starts_with("$ ") => Err(ShellError::Parse(String::from(text))),
+            Err(_) => shell.output(text),
+        };
+        result.map_err(|e| ShellError::Line {
+            line,
+            error: Box::new(e),
+        })?;
+        last = line;
+    }
+    shell.finish().map_err(|e| ShellError::Line {
+        line: last,
+        error: Box::new(e),
+    })?;
 
-    root
+    shell.tree.calc_sizes();
+
+    Ok(shell.tree)
+}
+
+/// How much has to go to leave at least `needed` free on a disk of the given
+/// size
+fn to_free(tree: &Tree, disk: usize, needed: usize) -> usize {
+    (tree[ROOT].size + needed).saturating_sub(disk)
+}
+
+/// The smallest directory below the root which would leave enough free if
+/// deleted, or `None` if there's enough free already
+fn smallest_to_free(tree: &Tree, disk: usize, needed: usize) -> Option<Id> {
+    let to_free = to_free(tree, disk, needed);
+    if to_free == 0 {
+        return None;
+    }
+    tree.dirs()
+        .filter(|id| *id != ROOT && tree[*id].size >= to_free)
+        .min_by_key(|id| tree[*id].size)
 }
 
 fn part1(input: &str) -> usize {
-    let root = parse_input(input);
-    root.size
+    let tree = parse_input(input).expect("invalid transcript");
+    tree.dirs()
+        .map(|id| tree[id].size)
+        .filter(|s| *s <= 100000)
+        .sum()
+}
+
+fn part2(input: &str) -> usize {
+    let tree = parse_input(input).expect("invalid transcript");
+    let dir = smallest_to_free(&tree, 70000000, 30000000).expect("not enough space");
+    tree[dir].size
+}
+
+/// Read the disk size, the space needed and the transcript for `free`
+fn space_and_tree(args: &[String]) -> Result<(usize, usize, Tree), String> {
+    let size = |i: usize| -> Result<usize, String> {
+        let arg = args.get(i).ok_or("missing size")?;
+        arg.parse().map_err(|_| format!("bad size {}", arg))
+    };
+    if args.len() > 3 {
+        return Err(String::from("only one file at a time"));
+    }
+    Ok((size(0)?, size(1)?, tree_from(args.get(2))?))
+}
+
+/// `free <disk> <needed> [file]`: what to delete to free up space on a
+/// different disk
+pub fn free(args: &[String]) {
+    let (disk, needed, tree) = match space_and_tree(args) {
+        Ok(found) => found,
+        Err(e) => return println!("{}\nusage: free <disk> <needed> [file]", e),
+    };
+    println!("used: {}", tree[ROOT].size);
+    if to_free(&tree, disk, needed) == 0 {
+        return println!("nothing needs deleting");
+    }
+    match smallest_to_free(&tree, disk, needed) {
+        Some(dir) => println!("delete {} ({})", tree.path(dir), tree[dir].size),
+        None => println!("no single directory frees enough"),
+    }
+}
+
+/// Read the tree from a transcript, or the puzzle input if none is given
+fn tree_from(path: Option<&String>) -> Result<Tree, String> {
+    let input = match path {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?
+        }
+        None => String::from(include_str!("input.txt")),
+    };
+    parse_input(&input).map_err(|e| e.to_string())
+}
+
+/// Read the options shared by `tree` and `du`, and the transcript to use
+fn view_and_tree(args: &[String]) -> Result<(View, Tree), String> {
+    let mut view = View::default();
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "-s" | "--sort" => view.by_size = true,
+            "-b" | "--bytes" => view.bytes = true,
+            "--min" => view.min = parse_size(value(arg)?).ok_or("bad --min")?,
+            "--max" => view.max = Some(parse_size(value(arg)?).ok_or("bad --max")?),
+            "-d" | "--max-depth" => {
+                view.max_depth = Some(value(arg)?.parse().map_err(|_| "bad --max-depth")?)
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(String::from("only one file at a time")),
+        }
+    }
+    Ok((view, tree_from(path)?))
+}
+
+const VIEW_OPTIONS: &str =
+    "[-s|--sort] [-b|--bytes] [--min SIZE] [--max SIZE] [-d|--max-depth N] [file]";
+
+/// `tree [options] [file]`: draw the filesystem from a transcript
+pub fn tree(args: &[String]) {
+    match view_and_tree(args) {
+        Ok((view, tree)) => print!("{}", render_tree(&tree, &view)),
+        Err(e) => println!("{}\nusage: tree {}", e, VIEW_OPTIONS),
+    }
+}
+
+/// `du [options] [file]`: list the directories in a transcript with their sizes
+pub fn du(args: &[String]) {
+    match view_and_tree(args) {
+        Ok((view, tree)) => print!("{}", render_du(&tree, &view)),
+        Err(e) => println!("{}\nusage: du {}", e, VIEW_OPTIONS),
+    }
+}
+
+/// Read the tests for `find`, and the transcript to look through
+fn query_and_tree(args: &[String]) -> Result<(Query, Tree), String> {
+    let mut query = Query::default();
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--name" => query.name = Some(value(arg)?.clone()),
+            "--ext" => query.extension = Some(value(arg)?.clone()),
+            "--min" => query.min_size = Some(parse_size(value(arg)?).ok_or("bad --min")?),
+            "--max" => query.max_size = Some(parse_size(value(arg)?).ok_or("bad --max")?),
+            "--min-depth" => {
+                query.min_depth = Some(value(arg)?.parse().map_err(|_| "bad --min-depth")?)
+            }
+            "--max-depth" => {
+                query.max_depth = Some(value(arg)?.parse().map_err(|_| "bad --max-depth")?)
+            }
+            "--type" => match value(arg)?.as_str() {
+                "f" => query.dir = Some(false),
+                "d" => query.dir = Some(true),
+                _ => return Err(String::from("--type must be f or d")),
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(String::from("only one file at a time")),
+        }
+    }
+    Ok((query, tree_from(path)?))
+}
+
+/// `find [options] [file]`: list the paths of the files in a transcript which
+/// pass every test given
+pub fn find(args: &[String]) {
+    match query_and_tree(args) {
+        Ok((query, tree)) => {
+            for path in find::find(&tree, &query) {
+                println!("{}", path);
+            }
+        }
+        Err(e) => println!(
+            "{}\nusage: find [--name GLOB] [--ext EXT] [--min SIZE] [--max SIZE] \
+             [--min-depth N] [--max-depth N] [--type f|d] [file]",
+            e
+        ),
+    }
+}
+
+/// `materialise [file [dir]]`: recreate the tree from a transcript on disk,
+/// under the temporary directory unless told where, and check the sizes
+/// against `du`
+pub fn materialise(args: &[String]) {
+    let tree = match tree_from(args.first()) {
+        Ok(tree) => tree,
+        Err(e) => return println!("{}", e),
+    };
+    let path = match args.get(1) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => disk::temp_path("tree"),
+    };
+    if let Err(e) = disk::materialise(&tree, &path) {
+        return println!("could not create {}: {}", path.display(), e);
+    }
+    println!("created {}", path.display());
+    match disk::check_du(&tree, &path) {
+        Ok(mismatches) if mismatches.is_empty() => println!("du agrees on every size"),
+        Ok(mismatches) => mismatches.iter().for_each(|m| println!("{}", m)),
+        Err(e) => println!("could not check with du: {}", e),
+    }
+}
+
+/// `transcript <dir>`: explore a real directory the way the puzzle does
+pub fn transcript(args: &[String]) {
+    let Some(dir) = args.first() else {
+        return println!("usage: transcript <dir>");
+    };
+    match disk::transcript(std::path::Path::new(dir)) {
+        Ok(text) => print!("{}", text),
+        Err(e) => println!("could not read {}: {}", dir, e),
+    }
 }
 
 pub fn main() {
     let input = include_str!("input.txt");
     println!("part 1: {}", part1(input));
+    println!("part 2: {}", part2(input));
 }
 
 #[cfg(test)]
@@ -140,6 +751,223 @@ mod test {
 
     #[test]
     fn test_size() {
-        assert_eq!(parse_input(INPUT).size, 48381165);
+        assert_eq!(parse_input(INPUT).unwrap()[ROOT].size, 48381165);
+    }
+
+    #[test]
+    fn test_dir_sizes() {
+        let tree = parse_input(INPUT).unwrap();
+        let mut sizes: Vec<_> = tree.dirs().map(|id| tree[id].size).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![584, 94853, 24933642, 48381165]);
+    }
+
+    #[test]
+    fn test_paths() {
+        let tree = parse_input(INPUT).unwrap();
+        let e = tree.resolve(ROOT, "/a/e").unwrap();
+        assert_eq!(tree[e].size, 584);
+        assert_eq!(tree.path(e), "/a/e");
+        assert_eq!(tree.resolve(e, "../../d/k"), tree.resolve(ROOT, "d/k"));
+        assert_eq!(tree.resolve(e, "/"), Some(ROOT));
+        assert_eq!(tree.resolve(e, "./i"), tree.resolve(ROOT, "a/e/i"));
+        assert_eq!(tree.resolve(ROOT, "/a/nope"), None);
+        assert_eq!(tree.resolve(ROOT, "/b.txt/x"), None);
+        assert_eq!(tree.resolve(ROOT, ".."), None);
+        assert_eq!(tree.path(ROOT), "/");
+    }
+
+    #[test]
+    fn test_walk() {
+        let tree = parse_input(INPUT).unwrap();
+        let paths: Vec<_> = tree.walk(ROOT).map(|id| tree.path(id)).collect();
+        assert_eq!(paths.len(), 14);
+        assert_eq!(paths[..4], ["/", "/a", "/a/e", "/a/e/i"]);
+        let a = tree.resolve(ROOT, "/a").unwrap();
+        let names: Vec<_> = tree.children(a).map(|id| &tree[id].name).collect();
+        assert_eq!(names, ["e", "f", "g", "h.lst"]);
+        let dirs: Vec<_> = tree.dirs().map(|id| tree.path(id)).collect();
+        assert_eq!(dirs, ["/", "/a", "/a/e", "/d"]);
+    }
+
+    #[test]
+    fn test_cmd() {
+        let cmd = |s: &str| Cmd::from_str(s).ok();
+        assert_eq!(cmd("$ cd /a/e"), Some(Cmd::Cd(String::from("/a/e"))));
+        assert_eq!(cmd("$ ls"), Some(Cmd::Ls(None)));
+        assert_eq!(cmd("$ ls .."), Some(Cmd::Ls(Some(String::from("..")))));
+        let paths = vec![String::from("x"), String::from("y")];
+        let mkdir = |parents| Cmd::Mkdir {
+            paths: paths.clone(),
+            parents,
+        };
+        assert_eq!(cmd("$ mkdir x y"), Some(mkdir(false)));
+        assert_eq!(cmd("$ mkdir -p x y"), Some(mkdir(true)));
+        let rm = |recursive, force| Cmd::Rm {
+            paths: paths.clone(),
+            recursive,
+            force,
+        };
+        assert_eq!(cmd("$ rm x y"), Some(rm(false, false)));
+        assert_eq!(cmd("$ rm -rf x y"), Some(rm(true, true)));
+        assert_eq!(cmd("$ rm -R x -f y"), Some(rm(true, true)));
+        assert_eq!(cmd("$ pwd"), Some(Cmd::Pwd));
+        assert_eq!(cmd("$ cd"), None);
+        assert_eq!(cmd("$ rm -r"), None);
+        assert_eq!(cmd("$ rm -i x"), None);
+        assert_eq!(cmd("$ mkdir -m x"), None);
+        assert_eq!(cmd("$ mkdir - x"), None);
+        assert_eq!(cmd("$ ls -l"), None);
+        assert_eq!(cmd("$ cat a"), None);
+        assert_eq!(cmd("dir a"), None);
+    }
+
+    #[test]
+    fn test_more_commands() {
+        let input = "\
+$ cd /
+$ mkdir a a/b /c
+$ cd a/b
+$ pwd
+/a/b
+$ ls /
+dir a
+dir c
+10 f
+$ ls ../..
+dir a
+dir c
+10 f
+$ rm -r /c
+$ rm ../../f
+$ rm -f /nope
+$ cd ../b/../..
+$ ls
+dir a
+$ ls a/b
+5 g
+";
+        let tree = parse_input(input).unwrap();
+        let paths: Vec<_> = tree.walk(ROOT).map(|id| tree.path(id)).collect();
+        assert_eq!(paths, ["/", "/a", "/a/b", "/a/b/g"]);
+        assert_eq!(tree[ROOT].size, 5);
+    }
+
+    #[test]
+    fn test_mkdir_parents() {
+        let input = "\
+$ mkdir -p x/y/z /x/w
+$ cd x/y/z
+$ mkdir -p . .. ../../../x/v
+$ cd /
+$ ls x
+dir v
+dir w
+dir y
+";
+        let tree = parse_input(input).unwrap();
+        let paths: Vec<_> = tree.dirs().map(|id| tree.path(id)).collect();
+        assert_eq!(paths, ["/", "/x", "/x/v", "/x/w", "/x/y", "/x/y/z"]);
+    }
+
+    #[test]
+    fn test_shell_errors() {
+        let error = |input: &str| match parse_input(input) {
+            Err(ShellError::Line { line, error }) => (line, *error),
+            result => panic!("expected an error, got {:?}", result),
+        };
+        let path = String::from;
+        assert_eq!(error("$ cd x"), (1, ShellError::NotFound(path("x"))));
+        assert_eq!(
+            error("$ ls\n5 f\n$ cd f"),
+            (3, ShellError::NotADir(path("f")))
+        );
+        assert_eq!(
+            error("$ mkdir a\n$ mkdir a"),
+            (2, ShellError::Exists(path("a")))
+        );
+        assert_eq!(
+            error("$ mkdir a\n$ cd a\n$ rm -r .."),
+            (3, ShellError::Busy(path("..")))
+        );
+        assert_eq!(error("$ rm x"), (1, ShellError::NotFound(path("x"))));
+        assert_eq!(
+            error("$ mkdir a\n$ rm a"),
+            (2, ShellError::IsADir(path("a")))
+        );
+        assert_eq!(
+            error("$ ls\n5 f\n$ mkdir -p f/g"),
+            (3, ShellError::NotADir(path("f/g")))
+        );
+        assert_eq!(
+            error("$ ls\n5 f\n$ ls\n6 f"),
+            (
+                4,
+                ShellError::Contradiction {
+                    path: path("/f"),
+                    listed: path("6 f")
+                }
+            )
+        );
+        assert_eq!(
+            error("$ ls\ndir f\n$ ls\n6 f"),
+            (
+                4,
+                ShellError::Contradiction {
+                    path: path("/f"),
+                    listed: path("6 f")
+                }
+            )
+        );
+        assert_eq!(
+            error("$ mkdir a\n$ ls\n5 f"),
+            (3, ShellError::Unlisted(path("/a")))
+        );
+        assert_eq!(
+            error("$ mkdir a\n$ cd a\n$ pwd\n/b"),
+            (
+                4,
+                ShellError::Pwd {
+                    cwd: path("/a"),
+                    printed: path("/b")
+                }
+            )
+        );
+        assert_eq!(error("$ cat x"), (1, ShellError::Parse(path("$ cat x"))));
+        assert_eq!(error("5 f"), (1, ShellError::Parse(path("5 f"))));
+        assert_eq!(
+            error("$ ls\nfile f"),
+            (2, ShellError::Parse(path("file f")))
+        );
+        assert_eq!(
+            error("$ ls\ndir .."),
+            (2, ShellError::Parse(path("dir ..")))
+        );
+        assert_eq!(error("$ ls\n1 a/b"), (2, ShellError::Parse(path("1 a/b"))));
+    }
+
+    #[test]
+    fn test_smallest_to_free() {
+        let tree = parse_input(INPUT).unwrap();
+        let path = |disk, needed| smallest_to_free(&tree, disk, needed).map(|id| tree.path(id));
+        assert_eq!(path(70000000, 30000000).as_deref(), Some("/d"));
+        assert_eq!(path(48000000, 1000).as_deref(), Some("/d"));
+        assert_eq!(path(48381165, 90000).as_deref(), Some("/a"));
+        assert_eq!(path(48400000, 20000).as_deref(), Some("/a"));
+        // Nothing needs deleting, or only deleting everything would do
+        assert_eq!(path(48381165, 0), None);
+        assert_eq!(path(100000000, 1000), None);
+        assert_eq!(path(48381165, 30000000), None);
+        assert_eq!(path(1000, 2000), None);
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(INPUT), 95437);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(INPUT), 24933642);
     }
 }
@@ -0,0 +1,234 @@
+use super::{Id, Tree, ROOT};
+
+/// What to include in a report, and how to lay it out
+#[derive(Debug, Default)]
+pub struct View {
+    /// Biggest first, rather than by name
+    pub by_size: bool,
+    pub min: usize,
+    pub max: Option<usize>,
+    /// How far below the root to go, with the root itself at depth 0
+    pub max_depth: Option<usize>,
+    /// Exact sizes rather than `du -h` style ones
+    pub bytes: bool,
+}
+
+impl View {
+    fn allows(&self, size: usize) -> bool {
+        size >= self.min && self.max.is_none_or(|max| size <= max)
+    }
+
+    fn size(&self, size: usize) -> String {
+        if self.bytes {
+            size.to_string()
+        } else {
+            human(size)
+        }
+    }
+
+    /// Put files in the order they should be shown
+    fn sort(&self, tree: &Tree, ids: &mut [Id]) {
+        if self.by_size {
+            // Stable, so equal sizes stay in order of name
+            ids.sort_by_key(|id| usize::MAX - tree[*id].size);
+        }
+    }
+}
+
+/// A size as `du -h` would show it: rounded up, in powers of 1024, with one
+/// decimal place below 10
+pub fn human(size: usize) -> String {
+    let mut scaled = size as f64;
+    for unit in ["K", "M", "G", "T", "P"] {
+        if scaled < 1024.0 && unit == "K" {
+            break;
+        }
+        scaled /= 1024.0;
+        if scaled < 1024.0 {
+            let tenths = (scaled * 10.0).ceil();
+            return if tenths < 100.0 {
+                format!("{:.1}{}", tenths / 10.0, unit)
+            } else {
+                format!("{}{}", scaled.ceil(), unit)
+            };
+        }
+    }
+    size.to_string()
+}
+
+/// Parse a size like `100000`, `4K` or `1.5M`
+pub fn parse_size(s: &str) -> Option<usize> {
+    let (number, scale): (_, u64) = match s.char_indices().last()? {
+        (i, 'K') => (&s[..i], 1 << 10),
+        (i, 'M') => (&s[..i], 1 << 20),
+        (i, 'G') => (&s[..i], 1 << 30),
+        (i, 'T') => (&s[..i], 1 << 40),
+        _ => (s, 1),
+    };
+    let number: f64 = number.parse().ok()?;
+    (number >= 0.0).then(|| (number * scale as f64).round() as usize)
+}
+
+/// Draw the tree like `tree` does, with each file's size next to it.  A
+/// directory is kept when something inside it passes the size filter, so that
+/// the structure still makes sense.
+pub fn render_tree(tree: &Tree, view: &View) -> String {
+    let depths = tree.depths();
+    let mut shown = vec![false; tree.files.len()];
+    for id in (0..tree.files.len()).rev() {
+        shown[id] = view.max_depth.is_none_or(|max| depths[id] <= max)
+            && (view.allows(tree[id].size) || tree.children(id).any(|c| shown[c]));
+    }
+
+    let mut out = format!("/ ({})\n", view.size(tree[ROOT].size));
+    draw(tree, view, &shown, ROOT, "", &mut out);
+    out
+}
+
+fn draw(tree: &Tree, view: &View, shown: &[bool], dir: Id, prefix: &str, out: &mut String) {
+    let mut children: Vec<_> = tree.children(dir).filter(|id| shown[*id]).collect();
+    view.sort(tree, &mut children);
+    for (i, id) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let file = &tree[*id];
+        let branch = if last { "└── " } else { "├── " };
+        let name = if file.is_dir() {
+            format!("{}/", file.name)
+        } else {
+            file.name.clone()
+        };
+        out.push_str(&format!(
+            "{}{}{} ({})\n",
+            prefix,
+            branch,
+            name,
+            view.size(file.size)
+        ));
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        draw(tree, view, shown, *id, &prefix, out);
+    }
+}
+
+/// List the directories with their sizes like `du` does: everything inside a
+/// directory comes before it, or biggest first when sorting by size
+pub fn render_du(tree: &Tree, view: &View) -> String {
+    let depths = tree.depths();
+    let mut dirs = vec![];
+    post_order(tree, ROOT, &mut dirs);
+    dirs.retain(|id| {
+        view.allows(tree[*id].size) && view.max_depth.is_none_or(|max| depths[*id] <= max)
+    });
+    view.sort(tree, &mut dirs);
+    dirs.iter()
+        .map(|id| format!("{}\t{}\n", view.size(tree[*id].size), tree.path(*id)))
+        .collect()
+}
+
+fn post_order(tree: &Tree, dir: Id, out: &mut Vec<Id>) {
+    for id in tree.children(dir).filter(|id| tree[*id].is_dir()) {
+        post_order(tree, id, out);
+    }
+    out.push(dir);
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse_input;
+    use super::*;
+
+    const INPUT: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_human() {
+        assert_eq!(human(584), "584");
+        assert_eq!(human(1024), "1.0K");
+        assert_eq!(human(1025), "1.1K");
+        assert_eq!(human(94853), "93K");
+        assert_eq!(human(8504156), "8.2M");
+        assert_eq!(human(48381165), "47M");
+        assert_eq!(human(10 << 30), "10G");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("100000"), Some(100000));
+        assert_eq!(parse_size("4K"), Some(4096));
+        assert_eq!(parse_size("1.5M"), Some(3 << 19));
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("-1"), None);
+    }
+
+    #[test]
+    fn test_tree() {
+        let tree = parse_input(INPUT).unwrap();
+        let view = View {
+            bytes: true,
+            ..Default::default()
+        };
+        let expected = "\
+/ (48381165)
+├── a/ (94853)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d/ (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+";
+        assert_eq!(render_tree(&tree, &view), expected);
+    }
+
+    #[test]
+    fn test_tree_filtered() {
+        let tree = parse_input(INPUT).unwrap();
+        let view = View {
+            by_size: true,
+            min: 7 << 20,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let expected = "\
+/ (47M)
+├── d/ (24M)
+├── b.txt (15M)
+└── c.dat (8.2M)
+";
+        assert_eq!(render_tree(&tree, &view), expected);
+
+        // Small files keep the directories they're in
+        let view = View {
+            max: Some(1000),
+            bytes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_tree(&tree, &view),
+            "/ (48381165)\n└── a/ (94853)\n    └── e/ (584)\n        └── i (584)\n"
+        );
+    }
+
+    #[test]
+    fn test_du() {
+        let tree = parse_input(INPUT).unwrap();
+        let view = View::default();
+        assert_eq!(
+            render_du(&tree, &view),
+            "584\t/a/e\n93K\t/a\n24M\t/d\n47M\t/\n"
+        );
+
+        let view = View {
+            by_size: true,
+            max_depth: Some(1),
+            max: Some(100000),
+            bytes: true,
+            ..Default::default()
+        };
+        assert_eq!(render_du(&tree, &view), "94853\t/a\n");
+    }
+}
@@ -1,3 +1,9 @@
+use std::time::Instant;
+
+mod render;
+
+use render::{scores_table, shading};
+
 fn parse_input(input: &str) -> Vec<Vec<u32>> {
     let mut forest = Vec::new();
     for line in input.lines() {
@@ -11,8 +17,9 @@ fn parse_input(input: &str) -> Vec<Vec<u32>> {
     forest
 }
 
-fn part1(input: &str) -> usize {
-    let forest = parse_input(input);
+/// The original count of visible trees, which looks all the way to the edge
+/// from every tree, kept as a reference for the faster version below
+fn count_visible_reference(forest: &[Vec<u32>]) -> usize {
     let mut count = 0;
     for (i, row) in forest.iter().enumerate() {
         for (j, tree) in row.iter().enumerate() {
@@ -30,8 +37,8 @@ fn part1(input: &str) -> usize {
     count
 }
 
-fn part2(input: &str) -> usize {
-    let forest = parse_input(input);
+/// The original best scenic score, kept as a reference like the count above
+fn best_score_reference(forest: &[Vec<u32>]) -> usize {
     let h = forest.len();
     let mut best = 0;
     for (i, row) in forest.iter().enumerate() {
@@ -57,6 +64,351 @@ fn part2(input: &str) -> usize {
     best
 }
 
+/// Which way to look from a tree, as a step in (row, column)
+type Direction = (isize, isize);
+
+/// Along rows and columns, as in the puzzle
+const FOUR: [Direction; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// Along diagonals as well
+const EIGHT: [Direction; 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+/// Like a knight's moves, looking past the trees in between
+const KNIGHT: [Direction; 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+/// Parse `4`, `8`, `knight`, or steps like `1,2;-2,1` (or with spaces between
+/// them instead).  There can be up to 32 directions, none of them standing
+/// still.
+fn parse_directions(spec: &str) -> Option<Vec<Direction>> {
+    let directions = match spec {
+        "4" => FOUR.to_vec(),
+        "8" => EIGHT.to_vec(),
+        "knight" => KNIGHT.to_vec(),
+        _ => spec
+            .split([';', ' '])
+            .filter(|s| !s.is_empty())
+            .map(|step| {
+                let (di, dj) = step.split_once(',')?;
+                Some((di.trim().parse().ok()?, dj.trim().parse().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()?,
+    };
+    let valid = (1..=32).contains(&directions.len()) && !directions.contains(&(0, 0));
+    valid.then_some(directions)
+}
+
+/// Where each tree can be seen from outside the forest, and its scenic score
+struct Survey {
+    directions: Vec<Direction>,
+    /// Bit `k` is set when the tree can be seen looking along `directions[k]`
+    /// from outside
+    visible: Vec<Vec<u32>>,
+    scores: Vec<Vec<usize>>,
+}
+
+impl Survey {
+    pub fn count_visible(&self) -> usize {
+        self.visible.iter().flatten().filter(|v| **v != 0).count()
+    }
+
+    /// The position of the tree with the highest score, the first in reading
+    /// order if there's a tie
+    pub fn best(&self) -> Option<(usize, usize)> {
+        let mut best = None;
+        for (i, row) in self.scores.iter().enumerate() {
+            for (j, score) in row.iter().enumerate() {
+                if best.is_none_or(|(_, s)| score > s) {
+                    best = Some(((i, j), score));
+                }
+            }
+        }
+        best.map(|(p, _)| p)
+    }
+}
+
+/// For each tree along a line, looking back towards the start: whether it's
+/// taller than everything before it, and how many trees it can see.  The
+/// stack holds the trees which could still block the view of later ones, so
+/// each tree goes on and comes off at most once.
+fn look_back(heights: &[u32]) -> Vec<(bool, usize)> {
+    let mut stack: Vec<usize> = Vec::new();
+    heights
+        .iter()
+        .enumerate()
+        .map(|(i, height)| {
+            while stack.last().is_some_and(|j| heights[*j] < *height) {
+                stack.pop();
+            }
+            let seen = match stack.last() {
+                Some(j) => (false, i - j),
+                None => (true, i),
+            };
+            stack.push(i);
+            seen
+        })
+        .collect()
+}
+
+/// The lines of trees to sweep along to look in a direction from each tree,
+/// each starting at the edge the direction leads to
+fn lines(h: usize, w: usize, (di, dj): Direction) -> Vec<Vec<(usize, usize)>> {
+    let step = |(i, j): (usize, usize), (di, dj): Direction| {
+        let (i, j) = (i.checked_add_signed(di)?, j.checked_add_signed(dj)?);
+        (i < h && j < w).then_some((i, j))
+    };
+    (0..h)
+        .flat_map(|i| (0..w).map(move |j| (i, j)))
+        .filter(|p| step(*p, (di, dj)).is_none())
+        .map(|start| std::iter::successors(Some(start), |p| step(*p, (-di, -dj))).collect())
+        .collect()
+}
+
+/// Work out visibility and scores for every tree in O(n²), by sweeping along
+/// every line once per direction
+fn survey(forest: &[Vec<u32>], directions: &[Direction]) -> Survey {
+    let h = forest.len();
+    let w = forest.first().map_or(0, |row| row.len());
+    let mut survey = Survey {
+        directions: directions.to_vec(),
+        visible: vec![vec![0; w]; h],
+        scores: vec![vec![1; w]; h],
+    };
+    let mut heights = Vec::new();
+    for (k, direction) in directions.iter().enumerate() {
+        for line in lines(h, w, *direction) {
+            heights.clear();
+            heights.extend(line.iter().map(|(i, j)| forest[*i][*j]));
+            for ((i, j), (visible, distance)) in line.into_iter().zip(look_back(&heights)) {
+                survey.visible[i][j] |= (visible as u32) << k;
+                // Plenty of directions on a big forest could overflow
+                survey.scores[i][j] = survey.scores[i][j].saturating_mul(distance);
+            }
+        }
+    }
+    survey
+}
+
+fn part1(input: &str) -> usize {
+    survey(&parse_input(input), &FOUR).count_visible()
+}
+
+fn part2(input: &str) -> usize {
+    let survey = survey(&parse_input(input), &FOUR);
+    survey.best().map_or(0, |(i, j)| survey.scores[i][j])
+}
+
+/// A square forest of pseudo-random digits, the same every time for a size
+fn generate(size: usize) -> Vec<Vec<u32>> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64 ^ size as u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 10) as u32
+    };
+    (0..size)
+        .map(|_| (0..size).map(|_| next()).collect())
+        .collect()
+}
+
+/// A square forest rising in rings towards the middle.  With heights beyond
+/// single digits every tree can see the edge, which is the worst case for
+/// looking from each tree in turn.
+fn pyramid(size: usize) -> Vec<Vec<u32>> {
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| i.min(j).min(size - 1 - i).min(size - 1 - j) as u32)
+                .collect()
+        })
+        .collect()
+}
+
+/// `bench [size...]`: time both ways of answering on generated square forests
+pub fn bench(args: &[String]) {
+    let Ok(mut sizes) = args
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<usize>, _>>()
+    else {
+        return println!("usage: bench [size...]");
+    };
+    if sizes.is_empty() {
+        sizes = vec![100, 200, 400];
+    }
+
+    for (size, kind) in sizes.iter().flat_map(|n| [(*n, "digits"), (*n, "pyramid")]) {
+        let forest = match kind {
+            "digits" => generate(size),
+            _ => pyramid(size),
+        };
+        let time = |name: &str, func: &dyn Fn() -> (usize, usize)| {
+            let start = Instant::now();
+            let answers = func();
+            let seconds = start.elapsed().as_secs_f64();
+            println!("{:>5} {:<8} {:<10} {:>8.3}s", size, kind, name, seconds);
+            answers
+        };
+        let reference = time("reference", &|| {
+            (
+                count_visible_reference(&forest),
+                best_score_reference(&forest),
+            )
+        });
+        let fast = time("stacks", &|| {
+            let survey = survey(&forest, &FOUR);
+            let best = survey.best().map_or(0, |(i, j)| survey.scores[i][j]);
+            (survey.count_visible(), best)
+        });
+        assert_eq!(reference, fast);
+    }
+}
+
+/// Read a forest from the given file, or the puzzle input
+fn forest_from(path: Option<&String>) -> Result<Vec<Vec<u32>>, String> {
+    let input = match path {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?
+        }
+        None => String::from(include_str!("input.txt")),
+    };
+    parse_forest(&input)
+}
+
+/// Check a forest given by hand before reading it: lines of digits, all the
+/// same length, ignoring blank lines at the end
+fn parse_forest(input: &str) -> Result<Vec<Vec<u32>>, String> {
+    let input = input.trim_end_matches(['\n', '\r']);
+    if !input
+        .lines()
+        .flat_map(|l| l.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(String::from("a forest is lines of digits"));
+    }
+    let forest = parse_input(input);
+    let width = forest.first().map_or(0, |row| row.len());
+    if let Some(i) = forest.iter().position(|row| row.len() != width) {
+        return Err(format!(
+            "line {} has {} trees, but the first has {}",
+            i + 1,
+            forest[i].len(),
+            width
+        ));
+    }
+    Ok(forest)
+}
+
+/// Take a `--dirs SPEC` option out of the arguments, leaving the rest in
+/// order.  The directions are the puzzle's four without one.
+fn directions_and_args(args: &[String]) -> Result<(Vec<Direction>, Vec<&String>), String> {
+    let mut directions = FOUR.to_vec();
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--dirs" {
+            let spec = args.next().ok_or("--dirs needs a value")?;
+            directions = parse_directions(spec).ok_or(format!(
+                "bad directions {:?}: use 4, 8, knight or steps like \"1,2;-2,1\"",
+                spec
+            ))?;
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((directions, rest))
+}
+
+/// `sight [--dirs SPEC] [file]`: answer both parts looking in other directions
+pub fn sight(args: &[String]) {
+    let result = directions_and_args(args).and_then(|(directions, rest)| {
+        let forest = forest_from(rest.first().copied())?;
+        Ok(survey(&forest, &directions))
+    });
+    let survey = match result {
+        Ok(survey) => survey,
+        Err(e) => return println!("{}\nusage: sight [--dirs SPEC] [file]", e),
+    };
+    println!("visible: {}", survey.count_visible());
+    if let Some((i, j)) = survey.best() {
+        println!(
+            "best: {} at row {}, column {}",
+            survey.scores[i][j],
+            i + 1,
+            j + 1
+        );
+    }
+}
+
+/// `scores [--dirs SPEC] [file]`: print the scenic score of every tree
+pub fn scores(args: &[String]) {
+    let result = directions_and_args(args).and_then(|(directions, rest)| {
+        let forest = forest_from(rest.first().copied())?;
+        Ok(survey(&forest, &directions))
+    });
+    match result {
+        Ok(survey) => print!("{}", scores_table(&survey)),
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// `shade [--dirs SPEC] [file]`: show where each tree can be seen from
+pub fn shade(args: &[String]) {
+    let result = directions_and_args(args).and_then(|(directions, rest)| {
+        let forest = forest_from(rest.first().copied())?;
+        Ok(survey(&forest, &directions))
+    });
+    match result {
+        Ok(survey) => print!("{}", shading(&survey)),
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// `heatmap <out.ppm> [scale] [file] [--dirs SPEC]`: draw the scenic scores as
+/// an image, with the best tree in red
+pub fn heatmap(args: &[String]) {
+    let usage = "usage: heatmap <out.ppm> [scale] [file] [--dirs SPEC]";
+    let (directions, args) = match directions_and_args(args) {
+        Ok(found) => found,
+        Err(e) => return println!("{}\n{}", e, usage),
+    };
+    let Some(out) = args.first() else {
+        return println!("{}", usage);
+    };
+    let scale = match args.get(1).map(|s| s.parse()) {
+        Some(Ok(n)) if n > 0 => n,
+        None => 4,
+        _ => return println!("{}", usage),
+    };
+    let forest = match forest_from(args.get(2).copied()) {
+        Ok(forest) => forest,
+        Err(e) => return println!("{}", e),
+    };
+    let image = render::heatmap(&survey(&forest, &directions), scale);
+    match std::fs::write(out, image) {
+        Ok(()) => println!("wrote {}", out),
+        Err(e) => println!("could not write {}: {}", out, e),
+    }
+}
+
 pub fn main() {
     let input = include_str!("input.txt");
     println!("part 1: {}", part1(input));
@@ -78,4 +430,126 @@ mod test {
     fn test_part2() {
         assert_eq!(part2(INPUT), 8);
     }
+
+    #[test]
+    fn test_look_back() {
+        let seen = look_back(&[3, 0, 3, 7, 3]);
+        assert_eq!(
+            seen,
+            [(true, 0), (false, 1), (false, 2), (true, 3), (false, 1)]
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(lines(2, 3, (0, -1))[1], [(1, 0), (1, 1), (1, 2)]);
+        assert_eq!(lines(2, 3, (1, 0))[2], [(1, 2), (0, 2)]);
+    }
+
+    #[test]
+    fn test_parse_forest() {
+        assert_eq!(
+            parse_forest("123\n456\n\n"),
+            Ok(vec![vec![1, 2, 3], vec![4, 5, 6]])
+        );
+        assert_eq!(
+            parse_forest("123\n45"),
+            Err(String::from("line 2 has 2 trees, but the first has 3"))
+        );
+        assert!(parse_forest("12a").is_err());
+    }
+
+    #[test]
+    fn test_parse_directions() {
+        assert_eq!(parse_directions("4"), Some(FOUR.to_vec()));
+        assert_eq!(parse_directions("knight"), Some(KNIGHT.to_vec()));
+        assert_eq!(
+            parse_directions("1,2;-2,1 0,-3"),
+            Some(vec![(1, 2), (-2, 1), (0, -3)])
+        );
+        assert_eq!(parse_directions(""), None);
+        assert_eq!(parse_directions("1,1;0,0"), None);
+        assert_eq!(parse_directions("1;2"), None);
+        assert_eq!(parse_directions(&"1,0;".repeat(33)), None);
+    }
+
+    /// Look from every tree in every direction in turn, like the original code
+    /// but for any directions
+    fn survey_reference(forest: &[Vec<u32>], directions: &[Direction]) -> (usize, usize) {
+        let h = forest.len() as isize;
+        let w = forest[0].len() as isize;
+        let mut visible = 0;
+        let mut best = 0;
+        for i in 0..h {
+            for j in 0..w {
+                let tree = forest[i as usize][j as usize];
+                let mut seen = false;
+                let mut score = 1;
+                for (di, dj) in directions {
+                    let (mut y, mut x) = (i + di, j + dj);
+                    let mut distance = 0;
+                    let mut blocked = false;
+                    while (0..h).contains(&y) && (0..w).contains(&x) {
+                        distance += 1;
+                        if forest[y as usize][x as usize] >= tree {
+                            blocked = true;
+                            break;
+                        }
+                        (y, x) = (y + di, x + dj);
+                    }
+                    seen |= !blocked;
+                    score *= distance;
+                }
+                visible += seen as usize;
+                best = best.max(score);
+            }
+        }
+        (visible, best)
+    }
+
+    #[test]
+    fn test_directions() {
+        let forests = [
+            parse_input(INPUT),
+            generate(23),
+            pyramid(12),
+            vec![vec![5; 6]; 3],
+        ];
+        let custom = [(0, 3), (-1, 2), (5, 5)];
+        for forest in forests {
+            for directions in [&FOUR[..], &EIGHT, &KNIGHT, &custom] {
+                let survey = survey(&forest, directions);
+                let best = survey.best().map(|(i, j)| survey.scores[i][j]).unwrap();
+                assert_eq!(
+                    (survey.count_visible(), best),
+                    survey_reference(&forest, directions)
+                );
+            }
+        }
+
+        // Adding diagonals, the middle tree of the example sees two trees up
+        // and to the right, and still can't be seen from outside
+        let survey = survey(&parse_input(INPUT), &EIGHT);
+        assert_eq!(survey.scores[2][2], 2);
+        assert_eq!(survey.visible[2][2], 0);
+    }
+
+    #[test]
+    fn test_against_reference() {
+        let forests = [
+            parse_input(INPUT),
+            parse_input(include_str!("input.txt")),
+            generate(1),
+            generate(17),
+            generate(40),
+            pyramid(9),
+            vec![vec![5; 6]; 3],
+        ];
+        for forest in forests {
+            let survey = survey(&forest, &FOUR);
+            let best = survey.best().map(|(i, j)| survey.scores[i][j]).unwrap();
+            assert_eq!(survey.count_visible(), count_visible_reference(&forest));
+            assert_eq!(best, best_score_reference(&forest));
+        }
+    }
 }
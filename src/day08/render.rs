@@ -0,0 +1,149 @@
+use super::{Direction, Survey, FOUR};
+
+/// The scenic score of every tree, lined up in columns
+pub fn scores_table(survey: &Survey) -> String {
+    let max = survey.scores.iter().flatten().max().copied().unwrap_or(0);
+    let width = max.to_string().len();
+    survey
+        .scores
+        .iter()
+        .map(|row| {
+            let cells: Vec<_> = row.iter().map(|s| format!("{:>1$}", s, width)).collect();
+            cells.join(" ") + "\n"
+        })
+        .collect()
+}
+
+/// Which edge or corner a tree can be seen from when looking along a
+/// direction, if it's straight or diagonal
+fn arrow(direction: Direction) -> Option<char> {
+    match direction {
+        (0, -1) => Some('<'),
+        (0, 1) => Some('>'),
+        (-1, 0) => Some('^'),
+        (1, 0) => Some('v'),
+        (-1, -1) => Some('↖'),
+        (-1, 1) => Some('↗'),
+        (1, -1) => Some('↙'),
+        (1, 1) => Some('↘'),
+        _ => None,
+    }
+}
+
+/// One character for each tree: `.` when it's hidden, or an arrow pointing at
+/// the edge or corner it can be seen from if there's just one.  Otherwise it's a hex
+/// digit of the directions it can be seen from when looking the puzzle's four
+/// ways (1 for left, 2 for right, 4 for up and 8 for down), or how many
+/// directions for any other set.
+pub fn shading(survey: &Survey) -> String {
+    let four = survey.directions == FOUR;
+    survey
+        .visible
+        .iter()
+        .map(|row| {
+            let mut line: String = row
+                .iter()
+                .map(|seen| {
+                    let single = (seen.count_ones() == 1)
+                        .then(|| arrow(survey.directions[seen.trailing_zeros() as usize]))
+                        .flatten();
+                    match (seen, single) {
+                        (0, _) => '.',
+                        (_, Some(arrow)) => arrow,
+                        _ if four => char::from_digit(*seen, 16).unwrap(),
+                        _ => char::from_digit(seen.count_ones().min(9), 10).unwrap(),
+                    }
+                })
+                .collect();
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+/// A binary PPM image of the scenic scores, with `scale` pixels to a side for
+/// each tree.  Scores are shaded on a log scale from black to white, since a
+/// few trees score far higher than the rest, and the best tree is red.
+pub fn heatmap(survey: &Survey, scale: usize) -> Vec<u8> {
+    let h = survey.scores.len();
+    let w = survey.scores.first().map_or(0, |row| row.len());
+    let max = survey.scores.iter().flatten().max().copied().unwrap_or(0);
+    let best = survey.best();
+
+    let mut image = format!("P6\n{} {}\n255\n", w * scale, h * scale).into_bytes();
+    for (i, row) in survey.scores.iter().enumerate() {
+        let pixels: Vec<[u8; 3]> = row
+            .iter()
+            .enumerate()
+            .map(|(j, score)| {
+                if best == Some((i, j)) {
+                    return [255, 0, 0];
+                }
+                let shade = match max {
+                    0 => 0.0,
+                    _ => (*score as f64).ln_1p() / (max as f64).ln_1p(),
+                };
+                [(shade * 255.0).round() as u8; 3]
+            })
+            .collect();
+        for _ in 0..scale {
+            for pixel in &pixels {
+                for _ in 0..scale {
+                    image.extend(pixel);
+                }
+            }
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{parse_input, survey, EIGHT, FOUR, KNIGHT};
+    use super::*;
+
+    const INPUT: &str = include_str!("test.txt");
+
+    #[test]
+    fn test_scores_table() {
+        let table = scores_table(&survey(&parse_input(INPUT), &FOUR));
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "0 0 0 0 0");
+        assert_eq!(lines[1], "0 1 4 1 0");
+        assert_eq!(lines[3], "0 1 8 3 0");
+    }
+
+    #[test]
+    fn test_shading() {
+        let expected = "\
+5^^76
+<56.>
+f>.>>
+<.9.f
+99vfa
+";
+        assert_eq!(shading(&survey(&parse_input(INPUT), &FOUR)), expected);
+
+        // Diagonals have arrows too, but anything else is counted
+        let forest = vec![vec![0, 9, 9], vec![9, 1, 9], vec![9, 9, 9]];
+        assert_eq!(shading(&survey(&forest, &EIGHT)), "545\n4↖3\n536\n");
+        assert_eq!(shading(&survey(&forest, &KNIGHT)), "666\n687\n676\n");
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let survey = survey(&parse_input(INPUT), &FOUR);
+        let image = heatmap(&survey, 2);
+        let header = b"P6\n10 10\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 10 * 10 * 3);
+
+        // The best tree is at row 3, column 2, which starts at pixel (6, 4)
+        let at = |y: usize, x: usize| &pixels[(y * 10 + x) * 3..][..3];
+        assert_eq!(at(6, 4), [255, 0, 0]);
+        assert_eq!(at(7, 5), [255, 0, 0]);
+        assert_eq!(at(0, 0), [0, 0, 0]);
+        assert_eq!(at(2, 4), [187, 187, 187]);
+    }
+}
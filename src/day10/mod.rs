@@ -40,7 +40,7 @@ fn part1(input: &str) -> i32 {
     for (x, cycle) in Program::from(input).zip(1..) {
         // Add signal strength if passing the specified cycles
         if cycle % 40 == 20 {
-            total += ((cycle / 40) * 40 + 20) as i32 * x;
+            total += ((cycle / 40) * 40 + 20) * x;
         }
     }
     total
@@ -57,7 +57,7 @@ fn part2(input: &str) -> String {
         }
 
         rows.last_mut().unwrap().push(match scanline - x {
-            -1 | 0 | 1 => '#',
+            -1..=1 => '#',
             _ => '.',
         });
     }
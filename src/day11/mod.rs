@@ -80,14 +80,11 @@ impl Monkey {
         for item in self.items.drain(..) {
             self.count += 1;
             let item = self.update.call(item) / worry;
-            let target = match item % self.test == 0 {
+            let target = match item.is_multiple_of(self.test) {
                 true => self.target.0,
                 false => self.target.1,
             };
-            result
-                .entry(target)
-                .or_insert_with(|| Vec::new())
-                .push(item);
+            result.entry(target).or_insert_with(Vec::new).push(item);
         }
         result
     }
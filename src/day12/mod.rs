@@ -1,11 +1,77 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 
-fn walk<F>(input: &str, from: F) -> usize
+/// Decides which steps between neighbouring cells are allowed, and what they
+/// cost
+trait Rules {
+    /// Cost of stepping from a cell of height `from` onto one of height `to`,
+    /// or `None` if the step isn't allowed
+    fn cost(&self, from: i32, to: i32) -> Option<usize>;
+
+    /// Whether diagonally adjacent cells count as neighbours
+    fn diagonal(&self) -> bool {
+        false
+    }
+}
+
+/// Any closure can be used as a set of rules, for quick experiments
+impl<F> Rules for F
+where
+    F: Fn(i32, i32) -> Option<usize>,
+{
+    fn cost(&self, from: i32, to: i32) -> Option<usize> {
+        self(from, to)
+    }
+}
+
+/// Configurable climbing rules.  The default is the one from the puzzle: climb
+/// at most one level, descend any amount, every step costs the same.
+#[derive(Debug, Clone)]
+struct Climb {
+    /// Most levels that can be climbed in one step, or `None` for no limit
+    pub max_climb: Option<i32>,
+    /// Most levels that can be descended in one step, or `None` for no limit
+    pub max_descent: Option<i32>,
+    /// Extra cost per level of height change, on top of 1 per step
+    pub per_level: usize,
+    /// Allow diagonal moves
+    pub diagonal: bool,
+}
+
+impl Default for Climb {
+    fn default() -> Self {
+        Climb {
+            max_climb: Some(1),
+            max_descent: None,
+            per_level: 0,
+            diagonal: false,
+        }
+    }
+}
+
+impl Rules for Climb {
+    fn cost(&self, from: i32, to: i32) -> Option<usize> {
+        let change = to - from;
+        if self.max_climb.is_some_and(|n| change > n)
+            || self.max_descent.is_some_and(|n| -change > n)
+        {
+            return None;
+        }
+        Some(1 + self.per_level * change.unsigned_abs() as usize)
+    }
+
+    fn diagonal(&self) -> bool {
+        self.diagonal
+    }
+}
+
+fn walk<F, R>(input: &str, from: F, rules: &R) -> Option<usize>
 where
     F: Fn(char) -> bool,
+    R: Rules,
 {
     // Parse input
-    let mut changed = HashSet::new();
+    let mut queue = BinaryHeap::new();
     let mut target = None;
     let heights: Vec<Vec<_>> = input
         .lines()
@@ -19,7 +85,7 @@ where
                         25
                     }
                     _ if from(c) => {
-                        changed.insert((x, y));
+                        queue.push(Reverse((0, x, y)));
                         0
                     }
                     _ => (c as i32) - 97,
@@ -29,53 +95,49 @@ where
         .collect();
     let target = target.expect("cannot parse input");
 
-    let height = heights.len();
-    let width = heights[0].len();
-    let mut steps = vec![vec![width * height; width]; height];
-    let mut step = 0;
-    while !changed.is_empty() {
-        let mut to_check = HashSet::new();
-        for (x, y) in changed.drain() {
-            // Record number of steps taken to get here
-            steps[y][x] = step;
-
-            // Find all neighbouring cells
-            let mut neighbours = Vec::new();
-            if x < width - 1 {
-                neighbours.push((x + 1, y));
-            }
-            if x > 0 {
-                neighbours.push((x - 1, y));
-            }
-            if y < height - 1 {
-                neighbours.push((x, y + 1));
-            }
-            if y > 0 {
-                neighbours.push((x, y - 1));
-            }
+    let height = heights.len() as isize;
+    let width = heights[0].len() as isize;
+    let mut offsets = vec![(1, 0), (-1, 0), (0, 1), (0, -1)];
+    if rules.diagonal() {
+        offsets.extend([(1, 1), (1, -1), (-1, 1), (-1, -1)]);
+    }
 
-            // Include the ones with a close enough height in the next iteration
-            let h = heights[y][x] + 1;
-            to_check.extend(
-                neighbours
-                    .iter()
-                    .filter(|(x, y)| heights[*y][*x] <= h && step < steps[*y][*x]),
-            );
+    // Dijkstra's algorithm, starting from every allowed starting cell at once
+    let mut costs = vec![vec![usize::MAX; width as usize]; height as usize];
+    while let Some(Reverse((cost, x, y))) = queue.pop() {
+        if cost >= costs[y][x] {
+            // Already got here at least as cheaply
+            continue;
+        }
+        costs[y][x] = cost;
+        if (x, y) == target {
+            return Some(cost);
         }
 
-        step += 1;
-        changed = to_check;
+        // Queue up all the neighbours that can be reached from here
+        for (dx, dy) in offsets.iter() {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || nx >= width || ny < 0 || ny >= height {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if let Some(step) = rules.cost(heights[y][x], heights[ny][nx]) {
+                if cost + step < costs[ny][nx] {
+                    queue.push(Reverse((cost + step, nx, ny)));
+                }
+            }
+        }
     }
 
-    steps[target.1][target.0]
+    None
 }
 
 fn part1(input: &str) -> usize {
-    walk(input, |c| c == 'S')
+    walk(input, |c| c == 'S', &Climb::default()).expect("no route found")
 }
 
 fn part2(input: &str) -> usize {
-    walk(input, |c| c == 'S' || c == 'a')
+    walk(input, |c| c == 'S' || c == 'a', &Climb::default()).expect("no route found")
 }
 
 pub fn main() {
@@ -99,4 +161,34 @@ mod test {
     fn test_part2() {
         assert_eq!(part2(INPUT), 29);
     }
+
+    #[test]
+    fn test_closure_rules() {
+        // Same as the default rules
+        let rules = |from, to| if to - from <= 1 { Some(1) } else { None };
+        assert_eq!(walk(INPUT, |c| c == 'S', &rules), Some(31));
+
+        // Nothing can be climbed, so the summit is out of reach
+        let rules = |from, to| if to <= from { Some(1) } else { None };
+        assert_eq!(walk(INPUT, |c| c == 'S', &rules), None);
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let rules = Climb {
+            diagonal: true,
+            ..Default::default()
+        };
+        assert_eq!(walk(INPUT, |c| c == 'S', &rules), Some(27));
+    }
+
+    #[test]
+    fn test_weighted() {
+        let rules = Climb {
+            max_descent: Some(1),
+            per_level: 2,
+            ..Default::default()
+        };
+        assert_eq!(walk(INPUT, |c| c == 'S', &rules), Some(81));
+    }
 }
@@ -111,7 +111,7 @@ fn simulate(mut grid: Grid<bool>) -> usize {
         // Loop as long as it is possible to place the sand
         while grid.get(x, y) == Some(&false) {
             // Try to move down, down-left, and down-right in that order
-            let options = vec![(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)];
+            let options = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)];
             let next = options
                 .iter()
                 .find(|(x, y)| grid.get(*x, *y) != Some(&true));
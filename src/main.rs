@@ -13,6 +13,9 @@ mod day12;
 mod day13;
 mod day14;
 
+/// An extra tool for a day, given the rest of the command line
+type Command = fn(&[String]);
+
 fn main() {
     let funcs: Vec<fn()> = vec![
         day01::main,
@@ -31,17 +34,53 @@ fn main() {
         day14::main,
     ];
 
-    let mut args = std::env::args();
-    args.next(); // Skip arg 0
-    if let Some(arg) = args.next() {
-        match arg
-            .parse::<usize>()
-            .ok()
-            .and_then(|i| i.checked_sub(1))
-            .and_then(|i| funcs.get(i))
-        {
-            Some(func) => func(),
-            _ => println!("could not run {}", arg),
+    // Extra tools, run as `aoc <day> <command> [args...]`
+    let commands: Vec<(usize, &str, Command)> = vec![
+        (1, "top", day01::top),
+        (1, "report", day01::report),
+        (2, "score", day02::score),
+        (2, "decode", day02::decode),
+        (2, "tournament", day02::tournament),
+        (3, "groups", day03::groups),
+        (4, "coverage", day04::coverage),
+        (4, "camp", day04::camp),
+        (5, "trace", day05::trace),
+        (5, "crane", day05::crane),
+        (5, "whatif", day05::whatif),
+        (6, "markers", day06::markers),
+        (6, "bench", day06::bench),
+        (7, "free", day07::free),
+        (7, "tree", day07::tree),
+        (7, "du", day07::du),
+        (7, "find", day07::find),
+        (7, "materialise", day07::materialise),
+        (7, "transcript", day07::transcript),
+        (8, "bench", day08::bench),
+        (8, "sight", day08::sight),
+        (8, "scores", day08::scores),
+        (8, "shade", day08::shade),
+        (8, "heatmap", day08::heatmap),
+    ];
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(arg) = args.first() {
+        let day = arg.parse::<usize>().ok();
+        if let Some(command) = args.get(1) {
+            match commands
+                .iter()
+                .find(|(d, name, _)| Some(*d) == day && name == command)
+            {
+                Some((_, _, func)) => func(&args[2..]),
+                _ => println!("could not run {} {}", arg, command),
+            }
+        } else {
+            match day
+                .and_then(|i| i.checked_sub(1))
+                .and_then(|i| funcs.get(i))
+            {
+                Some(func) => func(),
+                _ => println!("could not run {}", arg),
+            }
         }
     } else {
         for (i, func) in funcs.iter().enumerate() {